@@ -0,0 +1,178 @@
+use core::cell::Cell;
+use core::hash::{Hash, Hasher};
+
+use crate::compat::Vec;
+
+/// The number of bits allocated for each key the filter is sized for, giving
+/// a false positive rate of roughly 1%.
+const BITS_PER_KEY: usize = 10;
+
+/// The number of bits set for each key.
+const NUM_HASHES: u64 = 7;
+
+/// The smallest number of bits in a filter.
+const MIN_BITS: usize = 64;
+
+/// A Bloom filter over the keys of a single map in a `ChainMap`.
+///
+/// Keys can only be added to the filter, so removing a key from the map
+/// leaves a stale bit pattern behind. That is harmless, since a false
+/// positive only means the map itself is checked.
+#[derive(Clone, Debug)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    /// The number of keys that can be added before the false positive rate
+    /// grows past the target and the filter should be rebuilt.
+    capacity: usize,
+    len: usize,
+}
+
+impl BloomFilter {
+    /// Creates a filter containing every key in the iterator, sized with
+    /// room to spare for keys added later.
+    pub(crate) fn build<'a, K, I>(keys: I) -> Self
+    where
+        K: Hash + 'a,
+        I: ExactSizeIterator<Item = &'a K>,
+    {
+        let num_bits = (keys.len() * 2 * BITS_PER_KEY)
+            .max(MIN_BITS)
+            .next_power_of_two();
+        let mut filter = BloomFilter {
+            bits: vec![0; num_bits / 64],
+            capacity: num_bits / BITS_PER_KEY,
+            len: 0,
+        };
+        for key in keys {
+            filter.insert(hash_key(key));
+        }
+        filter
+    }
+
+    /// Returns `true` if more keys have been added than the filter was sized
+    /// for.
+    pub(crate) fn is_full(&self) -> bool {
+        self.len >= self.capacity
+    }
+
+    pub(crate) fn insert(&mut self, hash: u64) {
+        let mask = self.bits.len() as u64 * 64 - 1;
+        for bit in bit_indices(hash, mask) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+
+    /// Returns `false` if the key with the given hash is definitely not in
+    /// the map.
+    pub(crate) fn might_contain(&self, hash: u64) -> bool {
+        let mask = self.bits.len() as u64 * 64 - 1;
+        bit_indices(hash, mask).all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// Derives the bits for a key from a single hash using double hashing, so
+/// each key only needs to be hashed once no matter how many filters are
+/// checked.
+fn bit_indices(hash: u64, mask: u64) -> impl Iterator<Item = u64> {
+    let first = hash & 0xFFFF_FFFF;
+    let second = (hash >> 32) | 1;
+    (0..NUM_HASHES).map(move |i| first.wrapping_add(i.wrapping_mul(second)) & mask)
+}
+
+/// Hashes a key for use with a `BloomFilter`.
+///
+/// This uses a fixed hasher rather than the map's own `BuildHasher`, so the
+/// same hash can be used for every filter in the chain.
+pub(crate) fn hash_key<Q: Hash + ?Sized>(k: &Q) -> u64 {
+    let mut hasher = FilterHasher(FNV_OFFSET_BASIS);
+    k.hash(&mut hasher);
+    hasher.finish()
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// An FNV-1a hasher with a final mixing step, so that both halves of the
+/// hash used by `bit_indices` depend on every byte of the key.
+///
+/// This is used instead of the standard library's `DefaultHasher` so that
+/// filters are available without the `std` feature.
+struct FilterHasher(u64);
+
+impl Hasher for FilterHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        hash ^ (hash >> 33)
+    }
+}
+
+/// A key being looked up in a chain, whose filter hash is only computed the
+/// first time a map with a filter is checked.
+pub(crate) struct Probe<'a, Q: ?Sized> {
+    key: &'a Q,
+    hash: Cell<Option<u64>>,
+}
+
+impl<'a, Q: Hash + ?Sized> Probe<'a, Q> {
+    pub(crate) fn new(key: &'a Q) -> Self {
+        Probe {
+            key,
+            hash: Cell::new(None),
+        }
+    }
+
+    pub(crate) fn hash(&self) -> u64 {
+        match self.hash.get() {
+            Some(hash) => hash,
+            None => {
+                let hash = hash_key(self.key);
+                self.hash.set(Some(hash));
+                hash
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_has_no_false_negatives() {
+        let keys: Vec<u32> = (0..100).collect();
+        let mut filter = BloomFilter::build(keys[..10].iter());
+        assert!(!filter.is_full());
+
+        // Adding keys past the filter's capacity raises the false positive
+        // rate, but never causes false negatives
+        for key in &keys[10..] {
+            filter.insert(hash_key(key));
+        }
+        assert!(filter.is_full());
+        for key in &keys {
+            assert!(filter.might_contain(hash_key(key)));
+        }
+    }
+
+    #[test]
+    fn filter_rejects_most_absent_keys() {
+        let keys: Vec<u32> = (0..1000).collect();
+        let filter = BloomFilter::build(keys.iter());
+
+        let false_positives = (1000..11000u32)
+            .filter(|key| filter.might_contain(hash_key(key)))
+            .count();
+        assert!(false_positives < 500, "{} false positives", false_positives);
+    }
+}
@@ -0,0 +1,256 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::iter::FromIterator;
+use core::ops::Index;
+
+use crate::compat::{hash_map, HashMap, RandomState, Vec};
+
+/// A chain of borrowed [`HashMap`]s with a single view into the values.
+///
+/// Unlike [`ChainMap`], which owns its maps, a `ChainMapRef` only holds
+/// references to maps owned elsewhere, so large maps can be viewed through a
+/// chain without being cloned or moved. Maps are added in precedence order,
+/// so the first map added has the highest precedence.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::ChainMapRef;
+///
+/// let mut overrides = HashMap::new();
+/// overrides.insert("theme", "dark");
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme", "light");
+/// defaults.insert("font", "mono");
+///
+/// let mut chain = ChainMapRef::new();
+/// chain.push_map(&overrides);
+/// chain.push_map(&defaults);
+///
+/// assert_eq!(chain.get("theme"), Some(&"dark"));
+/// assert_eq!(chain["font"], "mono");
+/// assert_eq!(chain.source_of("font"), Some(1));
+///
+/// // The maps are still owned by the caller
+/// assert_eq!(defaults.len(), 2);
+/// ```
+///
+/// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ChainMapRef<'a, K, V, S = RandomState> {
+    inner: Vec<&'a HashMap<K, V, S>>,
+}
+
+impl<'a, K, V, S> ChainMapRef<'a, K, V, S> {
+    /// Creates an empty `ChainMapRef`.
+    pub fn new() -> Self {
+        ChainMapRef { inner: Vec::new() }
+    }
+
+    /// Adds a map to the lowest-precedence end of the chain.
+    pub fn push_map(&mut self, map: &'a HashMap<K, V, S>) {
+        self.inner.push(map);
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    pub fn pop_map(&mut self) -> Option<&'a HashMap<K, V, S>> {
+        self.inner.pop()
+    }
+
+    /// Returns the number of maps in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V, S> ChainMapRef<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().any(|map| map.contains_key(k))
+    }
+
+    /// Returns a reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    ///
+    /// The reference borrows from the map itself rather than the chain, so
+    /// it can outlive the `ChainMapRef`.
+    pub fn get<Q>(&self, k: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().filter_map(|map| map.get(k)).next()
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().position(|map| map.contains_key(k))
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.iter().all(|map| map.is_empty())
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order.
+    ///
+    /// Each key appears once, with its highest-precedence value.
+    pub fn iter(&self) -> ChainMapRefIter<'_, 'a, K, V, S> {
+        ChainMapRefIter {
+            maps: &self.inner,
+            index: 0,
+            current: None,
+        }
+    }
+}
+
+impl<'a, K, V, S> Clone for ChainMapRef<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ChainMapRef {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Default for ChainMapRef<'a, K, V, S> {
+    fn default() -> Self {
+        ChainMapRef::new()
+    }
+}
+
+impl<'a, K, Q, V, S> Index<&Q> for ChainMapRef<'a, K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<'a, K, V, S> FromIterator<&'a HashMap<K, V, S>> for ChainMapRef<'a, K, V, S> {
+    fn from_iter<I: IntoIterator<Item = &'a HashMap<K, V, S>>>(iter: I) -> Self {
+        ChainMapRef {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Debug for ChainMapRef<'a, K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `ChainMapRef`.
+///
+/// This `struct` is created by the [`iter`] method on [`ChainMapRef`]. See
+/// its documentation for more.
+///
+/// [`iter`]: struct.ChainMapRef.html#method.iter
+/// [`ChainMapRef`]: struct.ChainMapRef.html
+pub struct ChainMapRefIter<'b, 'a, K, V, S> {
+    maps: &'b [&'a HashMap<K, V, S>],
+    index: usize,
+    current: Option<hash_map::Iter<'a, K, V>>,
+}
+
+impl<'b, 'a, K, V, S> Clone for ChainMapRefIter<'b, 'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ChainMapRefIter {
+            maps: self.maps,
+            index: self.index,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'b, 'a, K, V, S> Iterator for ChainMapRefIter<'b, 'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let shadowing = &self.maps[..self.index];
+                let entry =
+                    current.find(|&(key, _)| !shadowing.iter().any(|map| map.contains_key(key)));
+                if entry.is_some() {
+                    return entry;
+                }
+                self.index += 1;
+            }
+            let map: &'a HashMap<K, V, S> = self.maps.get(self.index)?;
+            self.current = Some(map.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChainMap;
+
+    #[test]
+    fn borrowed_chain_matches_owned_chain() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("shared", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("shared", 2);
+        second_map.insert("second", 2);
+
+        let borrowed: ChainMapRef<_, _> = vec![&first_map, &second_map].into_iter().collect();
+        let owned: ChainMap<_, _> = vec![first_map.clone(), second_map.clone()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(borrowed.len(), owned.len());
+        for (key, value) in owned.iter() {
+            assert_eq!(borrowed.get(key), Some(value));
+            assert_eq!(borrowed.source_of(key), owned.source_of(key));
+        }
+
+        let mut entries: Vec<_> = borrowed.iter().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![(&"first", &1), (&"second", &2), (&"shared", &1)]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::iter::{FromIterator, Peekable};
+use core::ops::{Bound, Index, RangeBounds};
+
+use crate::compat::{btree_map, BTreeMap, Vec};
+
+/// A chain of [`BTreeMap`]s with a single, ordered view into the values.
+///
+/// This provides the same precedence semantics as a [`ChainMap`] with
+/// [`Precedence::FirstWins`], so the first map added to the chain has the
+/// highest precedence. Since the maps are sorted, the unified view can be
+/// iterated in key order, and [`range`] can scan a contiguous range of keys,
+/// such as every key with a common prefix.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use chain_map::ChainBTreeMap;
+///
+/// let mut locals = BTreeMap::new();
+/// locals.insert("b", "local");
+///
+/// let mut globals = BTreeMap::new();
+/// globals.insert("a", "global");
+/// globals.insert("b", "global");
+/// globals.insert("c", "global");
+///
+/// let mut chain = ChainBTreeMap::new();
+/// chain.push_map(locals);
+/// chain.push_map(globals);
+///
+/// assert_eq!(chain.get("b"), Some(&"local"));
+/// assert_eq!(
+///     chain.iter().collect::<Vec<_>>(),
+///     vec![(&"a", &"global"), (&"b", &"local"), (&"c", &"global")]
+/// );
+/// ```
+///
+/// [`BTreeMap`]: https://doc.rust-lang.org/std/collections/struct.BTreeMap.html
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`Precedence::FirstWins`]: enum.Precedence.html#variant.FirstWins
+/// [`range`]: #method.range
+#[derive(Clone)]
+pub struct ChainBTreeMap<K, V> {
+    inner: Vec<BTreeMap<K, V>>,
+}
+
+impl<K, V> ChainBTreeMap<K, V> {
+    /// Creates an empty `ChainBTreeMap`.
+    pub fn new() -> Self {
+        ChainBTreeMap { inner: Vec::new() }
+    }
+
+    /// Adds a map to the lowest-precedence end of the chain.
+    pub fn push_map(&mut self, map: BTreeMap<K, V>) {
+        self.inner.push(map);
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    pub fn pop_map(&mut self) -> Option<BTreeMap<K, V>> {
+        self.inner.pop()
+    }
+
+    /// Returns the number of maps in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K: Ord, V> ChainBTreeMap<K, V> {
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.iter().any(|map| map.contains_key(k))
+    }
+
+    /// Returns a reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.iter().filter_map(|map| map.get(k)).next()
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.inner.iter().position(|map| map.contains_key(k))
+    }
+
+    /// Inserts a key-value pair into the highest-precedence map in the chain,
+    /// adding an empty map first if the chain is empty.
+    ///
+    /// Returns the value previously associated with the key in the
+    /// highest-precedence map, if any.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        if self.inner.is_empty() {
+            self.inner.push(BTreeMap::new());
+        }
+        self.inner[0].insert(k, v)
+    }
+
+    /// Removes a key from the highest-precedence map that contains it,
+    /// returning the value at the key if the key was present in the chain.
+    ///
+    /// A value for the same key in a lower-precedence map will become
+    /// visible.
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.inner[index].remove(k)
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.iter().all(BTreeMap::is_empty)
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// ascending key order.
+    ///
+    /// Each key appears once, with its highest-precedence value.
+    pub fn iter(&self) -> BTreeIter<'_, K, V> {
+        self.range::<K, _>(..)
+    }
+
+    /// An iterator visiting the key-value pairs in the unified view whose
+    /// keys fall within the given range, in ascending key order.
+    ///
+    /// Each key appears once, with its highest-precedence value.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`BTreeMap::range`], if the start
+    /// of the range is greater than the end, or if they are equal and both
+    /// excluded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use std::ops::Bound;
+    /// use chain_map::ChainBTreeMap;
+    ///
+    /// let mut overrides = BTreeMap::new();
+    /// overrides.insert("net.port".to_string(), "8080");
+    ///
+    /// let mut defaults = BTreeMap::new();
+    /// defaults.insert("log.level".to_string(), "info");
+    /// defaults.insert("net.host".to_string(), "localhost");
+    /// defaults.insert("net.port".to_string(), "80");
+    ///
+    /// let mut chain = ChainBTreeMap::new();
+    /// chain.push_map(overrides);
+    /// chain.push_map(defaults);
+    ///
+    /// let net: Vec<_> = chain
+    ///     .range::<str, _>((Bound::Included("net."), Bound::Unbounded))
+    ///     .take_while(|&(key, _)| key.starts_with("net."))
+    ///     .map(|(key, value)| (key.as_str(), *value))
+    ///     .collect();
+    /// assert_eq!(net, vec![("net.host", "localhost"), ("net.port", "8080")]);
+    /// ```
+    ///
+    /// [`BTreeMap::range`]: https://doc.rust-lang.org/std/collections/struct.BTreeMap.html#method.range
+    pub fn range<Q, R>(&self, range: R) -> BTreeIter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let bounds = (range.start_bound(), range.end_bound());
+        BTreeIter {
+            ranges: self
+                .inner
+                .iter()
+                .map(|map| map.range::<Q, (Bound<&Q>, Bound<&Q>)>(bounds).peekable())
+                .collect(),
+        }
+    }
+}
+
+impl<K, V> Default for ChainBTreeMap<K, V> {
+    fn default() -> Self {
+        ChainBTreeMap::new()
+    }
+}
+
+impl<K, Q, V> Index<&Q> for ChainBTreeMap<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<K, V> FromIterator<BTreeMap<K, V>> for ChainBTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = BTreeMap<K, V>>>(iter: I) -> Self {
+        ChainBTreeMap {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for ChainBTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `ChainBTreeMap`, in ascending key order.
+///
+/// This `struct` is created by the [`iter`] and [`range`] methods on
+/// [`ChainBTreeMap`]. See their documentation for more.
+///
+/// [`iter`]: struct.ChainBTreeMap.html#method.iter
+/// [`range`]: struct.ChainBTreeMap.html#method.range
+/// [`ChainBTreeMap`]: struct.ChainBTreeMap.html
+pub struct BTreeIter<'a, K, V> {
+    ranges: Vec<Peekable<btree_map::Range<'a, K, V>>>,
+}
+
+impl<'a, K, V> Clone for BTreeIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        BTreeIter {
+            ranges: self.ranges.clone(),
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for BTreeIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        // Take the smallest key at the head of any map, preferring the
+        // highest-precedence map on ties, then skip past that key in every
+        // other map so it is only visited once.
+        let mut next: Option<(&'a K, &'a V)> = None;
+        for range in &mut self.ranges {
+            if let Some(&(key, value)) = range.peek() {
+                match next {
+                    Some((min, _)) if min <= key => {}
+                    _ => next = Some((key, value)),
+                }
+            }
+        }
+
+        let (key, _) = next?;
+        for range in &mut self.ranges {
+            if range.peek().map_or(false, |&(head, _)| head == key) {
+                range.next();
+            }
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_merges_maps_in_key_order() {
+        let mut first = BTreeMap::new();
+        first.insert(2, "first");
+        first.insert(6, "first");
+
+        let mut second = BTreeMap::new();
+        second.insert(1, "second");
+        second.insert(2, "second");
+        second.insert(5, "second");
+
+        let mut third = BTreeMap::new();
+        third.insert(3, "third");
+        third.insert(5, "third");
+        third.insert(7, "third");
+
+        let mut chain: ChainBTreeMap<_, _> = vec![first, second, third].into_iter().collect();
+        assert_eq!(chain.len(), 6);
+        assert_eq!(chain.source_of(&5), Some(1));
+
+        assert_eq!(
+            chain.range(2..6).collect::<Vec<_>>(),
+            vec![(&2, &"first"), (&3, &"third"), (&5, &"second")]
+        );
+        assert_eq!(
+            chain.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+            vec![1, 2, 3, 5, 6, 7]
+        );
+
+        assert_eq!(chain.remove(&5), Some("second"));
+        assert_eq!(chain[&5], "third");
+        assert_eq!(chain.insert(7, "override"), None);
+        assert_eq!(
+            chain.range(7..).collect::<Vec<_>>(),
+            vec![(&7, &"override")]
+        );
+    }
+}
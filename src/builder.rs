@@ -0,0 +1,173 @@
+use crate::compat::{HashMap, RandomState, String};
+
+use super::{ChainMap, Link, Links, Precedence};
+
+/// A builder for assembling a [`ChainMap`] from several maps.
+///
+/// This `struct` is created by the [`builder`] method on [`ChainMap`]. Maps
+/// are added to the chain in precedence order, so the first map added will
+/// have the highest precedence.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::ChainMap;
+///
+/// let mut overrides = HashMap::new();
+/// overrides.insert("theme", "dark");
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme", "light");
+/// defaults.insert("font", "mono");
+///
+/// let chain = ChainMap::builder()
+///     .capacity(2)
+///     .layer(overrides)
+///     .named_layer("defaults", defaults)
+///     .build();
+///
+/// assert_eq!(chain["theme"], "dark");
+/// assert_eq!(chain["font"], "mono");
+/// assert_eq!(chain.layer_index_by_name("defaults"), Some(1));
+/// ```
+///
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`builder`]: struct.ChainMap.html#method.builder
+pub struct ChainMapBuilder<K, V, S = RandomState> {
+    inner: Links<K, V, S>,
+    precedence: Precedence,
+}
+
+impl<K, V, S> ChainMapBuilder<K, V, S> {
+    /// Creates a new builder with no maps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a map to the lowest-precedence end of the chain being built.
+    pub fn layer(mut self, map: HashMap<K, V, S>) -> Self {
+        self.inner.push(map.into());
+        self
+    }
+
+    /// Adds a map with the given name to the lowest-precedence end of the
+    /// chain being built.
+    ///
+    /// See [`ChainMap::push_named_map`] for more.
+    ///
+    /// [`ChainMap::push_named_map`]: struct.ChainMap.html#method.push_named_map
+    pub fn named_layer<N>(mut self, name: N, map: HashMap<K, V, S>) -> Self
+    where
+        N: Into<String>,
+    {
+        self.inner.push(Link {
+            map,
+            name: Some(name.into()),
+            priority: 0,
+            tombstones: None,
+            enabled: true,
+            bloom: None,
+        });
+        self
+    }
+
+    /// Ensures the chain being built will be able to hold at least
+    /// `capacity` maps without reallocating.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        let additional = capacity.saturating_sub(self.inner.len());
+        self.inner.reserve(additional);
+        self
+    }
+
+    /// Sets the precedence of the chain being built.
+    ///
+    /// With [`Precedence::LastWins`], the maps added to the builder are given
+    /// the reverse precedence, so the last map added has the highest
+    /// precedence. The built chain will also use this precedence for maps
+    /// added to it later.
+    ///
+    /// [`Precedence::LastWins`]: enum.Precedence.html#variant.LastWins
+    pub fn precedence(mut self, precedence: Precedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
+
+    /// Builds the `ChainMap` from the maps added to the builder.
+    pub fn build(mut self) -> ChainMap<K, V, S> {
+        if self.precedence == Precedence::LastWins {
+            self.inner.reverse();
+        }
+
+        ChainMap {
+            inner: self.inner,
+            precedence: self.precedence,
+        }
+    }
+}
+
+impl<K, V, S> Default for ChainMapBuilder<K, V, S> {
+    fn default() -> Self {
+        ChainMapBuilder {
+            inner: Links::new(),
+            precedence: Precedence::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_preserves_layer_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+        second_map.insert("other", 2);
+
+        let chain = ChainMapBuilder::new()
+            .layer(first_map.clone())
+            .layer(second_map.clone())
+            .build();
+
+        let expected: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        assert_eq!(chain, expected);
+        assert_eq!(chain.get("key"), Some(&1));
+    }
+
+    #[test]
+    fn named_layer_adds_name() {
+        let chain: ChainMap<&str, i32> = ChainMapBuilder::new()
+            .layer(HashMap::new())
+            .named_layer("env", HashMap::new())
+            .build();
+
+        assert_eq!(chain.layer_name(0), None);
+        assert_eq!(chain.layer_name(1), Some("env"));
+    }
+
+    #[test]
+    fn capacity_reserves_space_for_layers() {
+        let builder: ChainMapBuilder<&str, i32> = ChainMapBuilder::new().capacity(10);
+        assert!(builder.inner.capacity() >= 10);
+
+        let chain = builder.layer(HashMap::new()).build();
+        assert_eq!(chain.layer_sizes(), vec![0]);
+    }
+
+    #[test]
+    fn last_wins_reverses_layer_order() {
+        let chain: ChainMap<_, _> = ChainMapBuilder::new()
+            .layer(vec![("key", 1)].into_iter().collect())
+            .named_layer("overrides", vec![("key", 2)].into_iter().collect())
+            .precedence(Precedence::LastWins)
+            .build();
+
+        assert_eq!(chain.get("key"), Some(&2));
+        assert_eq!(chain.layer_index_by_name("overrides"), Some(0));
+        assert_eq!(chain.precedence(), Precedence::LastWins);
+    }
+}
@@ -0,0 +1,579 @@
+use core::borrow::Borrow;
+use core::cell::{Cell, RefCell};
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::ops::{Deref, DerefMut, Index};
+
+use crate::compat::{HashMap, RandomState, ToOwned, Vec};
+
+use super::ChainMap;
+
+/// A `ChainMap` that remembers which map provided the value for each of the
+/// keys it has most recently looked up.
+///
+/// This `struct` is created by the [`with_lookup_cache`] method on
+/// [`ChainMap`]. The first lookup of a key searches the chain as usual and
+/// records the index of the map that contains it, or that no map does, so
+/// later lookups of the same key only need to check that one map. The cache
+/// holds at most `capacity` keys, evicting the least recently used key when
+/// it is full, so its memory use stays bounded however many distinct keys
+/// are looked up. The number of cache hits and misses is tracked and can be
+/// read with [`cache_stats`].
+///
+/// A wrapper created by [`with_negative_cache`] only caches the keys that
+/// are missing from every map in the chain. Lookups of missing keys normally
+/// check every map, so repeated lookups of them are always the worst case,
+/// while keys that are found are looked up exactly as they are on the chain.
+///
+/// The wrapper dereferences to the underlying chain, so every method of
+/// [`ChainMap`] is available. Any mutable access to the chain clears the
+/// cache, so it can never return stale results. Because the cache is updated
+/// during lookups through a shared reference, a `CachingChainMap` cannot be
+/// shared between threads.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::ChainMap;
+///
+/// let mut overrides = HashMap::new();
+/// overrides.insert("theme", "dark");
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme", "light");
+/// defaults.insert("font", "mono");
+///
+/// let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+/// let mut cached = chain.with_lookup_cache(100);
+///
+/// assert_eq!(cached.get(&"font"), Some(&"mono"));
+/// assert_eq!(cached.source_of(&"font"), Some(1));
+///
+/// // Mutating the chain clears the cache
+/// cached.insert("font", "serif");
+/// assert_eq!(cached.get(&"font"), Some(&"serif"));
+/// assert_eq!(cached.source_of(&"font"), Some(0));
+///
+/// let stats = cached.cache_stats();
+/// assert_eq!(stats.hits(), 2);
+/// assert_eq!(stats.misses(), 2);
+/// ```
+///
+/// [`with_lookup_cache`]: struct.ChainMap.html#method.with_lookup_cache
+/// [`with_negative_cache`]: struct.ChainMap.html#method.with_negative_cache
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`cache_stats`]: #method.cache_stats
+pub struct CachingChainMap<K, V, S = RandomState> {
+    chain: ChainMap<K, V, S>,
+    cache: RefCell<Lru<K>>,
+    misses_only: bool,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+impl<K, V, S> CachingChainMap<K, V, S>
+where
+    K: Hash + Eq,
+{
+    /// Wraps the chain with an empty lookup cache that holds at most
+    /// `capacity` keys.
+    ///
+    /// A capacity of 0 disables the cache, so every lookup searches the
+    /// chain.
+    pub fn new(chain: ChainMap<K, V, S>, capacity: usize) -> Self {
+        CachingChainMap {
+            chain,
+            cache: RefCell::new(Lru::new(capacity)),
+            misses_only: false,
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    /// Wraps the chain with an empty cache that holds at most `capacity`
+    /// keys that are missing from every map in the chain.
+    ///
+    /// Keys that are found in the chain are never cached, so lookups of them
+    /// always search the chain and are counted as misses.
+    pub fn negative(chain: ChainMap<K, V, S>, capacity: usize) -> Self {
+        CachingChainMap {
+            misses_only: true,
+            ..CachingChainMap::new(chain, capacity)
+        }
+    }
+
+    /// Removes all of the cached lookups.
+    ///
+    /// The hit and miss counts are not affected, see [`reset_cache_stats`].
+    ///
+    /// [`reset_cache_stats`]: #method.reset_cache_stats
+    pub fn clear_cache(&mut self) {
+        self.cache.get_mut().clear();
+    }
+}
+
+impl<K, V, S> CachingChainMap<K, V, S> {
+    /// Returns a reference to the underlying chain.
+    pub fn chain(&self) -> &ChainMap<K, V, S> {
+        &self.chain
+    }
+
+    /// Consumes the `CachingChainMap`, returning the underlying chain.
+    pub fn into_chain(self) -> ChainMap<K, V, S> {
+        self.chain
+    }
+
+    /// Returns the maximum number of keys the cache will hold.
+    pub fn cache_capacity(&self) -> usize {
+        self.cache.borrow().capacity
+    }
+
+    /// Returns the number of cache hits and misses since the wrapper was
+    /// created, or since the counts were last reset.
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+        }
+    }
+
+    /// Resets the hit and miss counts to zero.
+    pub fn reset_cache_stats(&self) {
+        self.hits.set(0);
+        self.misses.set(0);
+    }
+}
+
+impl<K, V, S> CachingChainMap<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    S: BuildHasher,
+{
+    /// Returns the index of the map that provides the value for the given
+    /// key, using the cache if the key has been looked up recently.
+    ///
+    /// The key may be any borrowed form of the chain's key type, but it must
+    /// be convertible back to the key type so that it can be stored in the
+    /// cache. See [`ChainMap::source_of`] for more.
+    ///
+    /// [`ChainMap::source_of`]: struct.ChainMap.html#method.source_of
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        if let Some(source) = self.cache.borrow_mut().get(k) {
+            self.hits.set(self.hits.get() + 1);
+            return source;
+        }
+
+        self.misses.set(self.misses.get() + 1);
+        let source = self.chain.source_of(k);
+        if source.is_none() || !self.misses_only {
+            self.cache.borrow_mut().insert(k.to_owned(), source);
+        }
+        source
+    }
+
+    /// Returns `true` if the cache records that the key is missing from
+    /// every map in the chain.
+    ///
+    /// A key that hasn't been looked up recently is not known to be missing,
+    /// even if it isn't in the chain. Checking the cache doesn't count as a
+    /// lookup, so it doesn't affect the hit and miss counts or which key is
+    /// evicted next.
+    pub fn is_known_absent<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.borrow().peek(k) == Some(None)
+    }
+
+    /// Returns `true` if the chain contains a value for the given key.
+    ///
+    /// See [`ChainMap::contains_key`] for more.
+    ///
+    /// [`ChainMap::contains_key`]: struct.ChainMap.html#method.contains_key
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.source_of(k).is_some()
+    }
+
+    /// Returns a reference to the highest-precedence value associated with
+    /// the given key.
+    ///
+    /// See [`ChainMap::get`] for more.
+    ///
+    /// [`ChainMap::get`]: struct.ChainMap.html#method.get
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let source = self.source_of(k)?;
+        self.chain.inner[source].map.get(k)
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for CachingChainMap<K, V, S>
+where
+    K: Hash + Eq + Clone + Borrow<Q>,
+    Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<K, V, S> Deref for CachingChainMap<K, V, S> {
+    type Target = ChainMap<K, V, S>;
+
+    fn deref(&self) -> &ChainMap<K, V, S> {
+        &self.chain
+    }
+}
+
+impl<K, V, S> DerefMut for CachingChainMap<K, V, S>
+where
+    K: Hash + Eq,
+{
+    fn deref_mut(&mut self) -> &mut ChainMap<K, V, S> {
+        self.cache.get_mut().clear();
+        &mut self.chain
+    }
+}
+
+impl<K, V, S> Clone for CachingChainMap<K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        CachingChainMap {
+            chain: self.chain.clone(),
+            cache: self.cache.clone(),
+            misses_only: self.misses_only,
+            hits: self.hits.clone(),
+            misses: self.misses.clone(),
+        }
+    }
+}
+
+impl<K, V, S> Debug for CachingChainMap<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingChainMap")
+            .field("chain", &self.chain)
+            .field("stats", &self.cache_stats())
+            .finish()
+    }
+}
+
+/// The number of cache hits and misses for a `CachingChainMap`.
+///
+/// This `struct` is created by the [`cache_stats`] method on
+/// [`CachingChainMap`].
+///
+/// [`cache_stats`]: struct.CachingChainMap.html#method.cache_stats
+/// [`CachingChainMap`]: struct.CachingChainMap.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CacheStats {
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheStats {
+    /// Returns the number of lookups that were answered by the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups that had to search the chain.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the fraction of lookups that were answered by the cache, or
+    /// `None` if there have been no lookups.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            None
+        } else {
+            Some(self.hits as f64 / total as f64)
+        }
+    }
+}
+
+/// A fixed-capacity map from keys to map indexes that evicts the least
+/// recently used key when full.
+///
+/// The entries form a doubly-linked list, stored in `nodes` and ordered from
+/// most to least recently used, so every operation is constant time.
+#[derive(Clone)]
+struct Lru<K> {
+    capacity: usize,
+    index: HashMap<K, usize>,
+    nodes: Vec<Node<K>>,
+    head: Option<usize>,
+    tail: Option<usize>,
+}
+
+#[derive(Clone)]
+struct Node<K> {
+    key: K,
+    source: Option<usize>,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+impl<K> Lru<K>
+where
+    K: Hash + Eq,
+{
+    fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            index: HashMap::new(),
+            nodes: Vec::new(),
+            head: None,
+            tail: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.index.clear();
+        self.nodes.clear();
+        self.head = None;
+        self.tail = None;
+    }
+
+    /// Returns the cached source for the key, marking it as most recently
+    /// used.
+    fn get<Q>(&mut self, k: &Q) -> Option<Option<usize>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = *self.index.get(k)?;
+        self.unlink(slot);
+        self.push_front(slot);
+        Some(self.nodes[slot].source)
+    }
+
+    /// Returns the cached source for the key without marking it as used.
+    fn peek<Q>(&self, k: &Q) -> Option<Option<usize>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(k).map(|&slot| self.nodes[slot].source)
+    }
+
+    fn insert(&mut self, k: K, source: Option<usize>)
+    where
+        K: Clone,
+    {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let slot = match self.tail {
+            Some(slot) if self.nodes.len() == self.capacity => {
+                // Reuse the least recently used node for the new key
+                self.unlink(slot);
+                self.index.remove(&self.nodes[slot].key);
+                self.nodes[slot].key = k.clone();
+                self.nodes[slot].source = source;
+                slot
+            }
+            _ => {
+                self.nodes.push(Node {
+                    key: k.clone(),
+                    source,
+                    prev: None,
+                    next: None,
+                });
+                self.nodes.len() - 1
+            }
+        };
+
+        self.index.insert(k, slot);
+        self.push_front(slot);
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let (prev, next) = (self.nodes[slot].prev, self.nodes[slot].next);
+        match prev {
+            Some(prev) => self.nodes[prev].next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.nodes[next].prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        self.nodes[slot].prev = None;
+        self.nodes[slot].next = self.head;
+        match self.head {
+            Some(head) => self.nodes[head].prev = Some(slot),
+            None => self.tail = Some(slot),
+        }
+        self.head = Some(slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::String;
+
+    #[test]
+    fn cache_records_hits_and_misses() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        let cached = chain.with_lookup_cache(8);
+
+        assert_eq!(cached.get(&"second"), Some(&2));
+        assert_eq!(cached.get(&"missing"), None);
+        assert!(cached.contains_key(&"first"));
+
+        let mut cache = cached.cache.borrow_mut();
+        assert_eq!(cache.index.len(), 3);
+        assert_eq!(cache.get(&"first"), Some(Some(0)));
+        assert_eq!(cache.get(&"second"), Some(Some(1)));
+        assert_eq!(cache.get(&"missing"), Some(None));
+    }
+
+    #[test]
+    fn mutable_access_clears_cache() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("key", 1)].into_iter().collect());
+
+        let mut cached = chain.with_lookup_cache(8);
+        assert_eq!(cached.get(&"missing"), None);
+        assert_eq!(cached.source_of(&"key"), Some(0));
+
+        cached.push_front_map(vec![("missing", 2)].into_iter().collect());
+        assert!(cached.cache.borrow().index.is_empty());
+
+        assert_eq!(cached[&"missing"], 2);
+        assert_eq!(cached.source_of(&"key"), Some(1));
+    }
+
+    #[test]
+    fn lookups_take_borrowed_keys() {
+        let mut map = HashMap::new();
+        map.insert(String::from("theme"), "dark");
+
+        let chain: ChainMap<_, _> = vec![map].into_iter().collect();
+        let cached = chain.with_lookup_cache(8);
+
+        assert_eq!(cached.get("theme"), Some(&"dark"));
+        assert_eq!(cached["theme"], "dark");
+        assert!(!cached.contains_key("font"));
+        assert_eq!(cached.cache.borrow().index.len(), 2);
+    }
+
+    #[test]
+    fn stats_track_hits_and_misses() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("first", 1), ("second", 2)].into_iter().collect());
+
+        let mut cached = chain.with_lookup_cache(1);
+        assert_eq!(cached.cache_stats().hit_rate(), None);
+
+        assert_eq!(cached.get(&"first"), Some(&1));
+        assert_eq!(cached.get(&"first"), Some(&1));
+        assert_eq!(cached.get(&"second"), Some(&2));
+        assert_eq!(cached.get(&"first"), Some(&1));
+
+        let stats = cached.cache_stats();
+        assert_eq!((stats.hits(), stats.misses()), (1, 3));
+        assert_eq!(stats.hit_rate(), Some(0.25));
+
+        cached.insert("first", 10);
+        assert_eq!(cached[&"first"], 10);
+        assert_eq!(cached.cache_stats().misses(), 4);
+
+        cached.reset_cache_stats();
+        assert_eq!(cached.cache_stats().hits(), 0);
+    }
+
+    #[test]
+    fn negative_cache_only_records_missing_keys() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        let mut cached = chain.with_negative_cache(8);
+
+        assert_eq!(cached.get(&"second"), Some(&2));
+        assert!(!cached.contains_key(&"missing"));
+        assert_eq!(cached.source_of(&"other"), None);
+        assert_eq!(cached.source_of(&"missing"), None);
+
+        assert_eq!(cached.cache.borrow().index.len(), 2);
+        assert!(cached.is_known_absent(&"missing"));
+        assert!(cached.is_known_absent(&"other"));
+        assert!(!cached.is_known_absent(&"second"));
+
+        let stats = cached.cache_stats();
+        assert_eq!((stats.hits(), stats.misses()), (1, 3));
+
+        cached.insert("missing", 3);
+        assert!(!cached.is_known_absent(&"missing"));
+        assert_eq!(cached[&"missing"], 3);
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", Some(0));
+        lru.insert("b", Some(1));
+
+        assert_eq!(lru.get(&"a"), Some(Some(0)));
+
+        lru.insert("c", None);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"a"), Some(Some(0)));
+        assert_eq!(lru.get(&"c"), Some(None));
+        assert_eq!(lru.index.len(), 2);
+    }
+
+    #[test]
+    fn zero_capacity_disables_cache() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("key", 1)].into_iter().collect());
+
+        let cached = chain.with_lookup_cache(0);
+        assert_eq!(cached.get(&"key"), Some(&1));
+        assert_eq!(cached.get(&"key"), Some(&1));
+        assert_eq!(cached.cache_stats().misses(), 2);
+        assert!(cached.cache.borrow().index.is_empty());
+    }
+}
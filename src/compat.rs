@@ -0,0 +1,45 @@
+//! The collection and allocation types used by the rest of the crate.
+//!
+//! With the `std` feature these come from the standard library. Without it,
+//! they come from `alloc`, and the maps in a chain are backed by
+//! `hashbrown`.
+
+#[cfg(feature = "std")]
+pub(crate) use std::borrow::{Cow, ToOwned};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::hash_map::{self, RandomState};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::hash_set;
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{btree_map, BTreeMap, HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::rc::Rc;
+#[cfg(feature = "std")]
+pub(crate) use std::string::String;
+#[cfg(feature = "std")]
+pub(crate) use std::sync::Arc;
+#[cfg(all(feature = "std", not(feature = "smallvec")))]
+pub(crate) use std::vec;
+#[cfg(feature = "std")]
+pub(crate) use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::borrow::{Cow, ToOwned};
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::collections::{btree_map, BTreeMap};
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::rc::Rc;
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::string::String;
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(all(not(feature = "std"), not(feature = "smallvec")))]
+pub(crate) use alloc::vec;
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::DefaultHashBuilder as RandomState;
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{hash_map, hash_set};
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
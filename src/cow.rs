@@ -0,0 +1,343 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::ops::Index;
+
+use crate::compat::{hash_map, Cow, HashMap, RandomState, Vec};
+
+/// A chain of copy-on-write [`HashMap`]s with a single view into the values.
+///
+/// Each map in the chain is either borrowed from elsewhere or owned by the
+/// chain. Reads never copy a map, while a write to a borrowed map first
+/// clones it, so a chain can share large source maps and only pay for a copy
+/// of the maps that are actually changed. Maps are added in precedence order,
+/// so the first map added has the highest precedence.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::CowChainMap;
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme", "light");
+/// defaults.insert("font", "mono");
+///
+/// let mut chain = CowChainMap::new();
+/// chain.push_borrowed_map(&defaults);
+///
+/// // Writes go to a new, owned map in front of the borrowed one
+/// chain.push_front_map(HashMap::new());
+/// chain.insert("theme", "dark");
+///
+/// assert_eq!(chain.get("theme"), Some(&"dark"));
+/// assert_eq!(chain.get("font"), Some(&"mono"));
+/// assert_eq!(chain.is_borrowed(1), Some(true));
+/// ```
+///
+/// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+pub struct CowChainMap<'a, K, V, S = RandomState>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    inner: Vec<Cow<'a, HashMap<K, V, S>>>,
+}
+
+impl<'a, K, V, S> CowChainMap<'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    /// Creates an empty `CowChainMap`.
+    pub fn new() -> Self {
+        CowChainMap { inner: Vec::new() }
+    }
+
+    /// Adds an owned map to the lowest-precedence end of the chain.
+    pub fn push_map(&mut self, map: HashMap<K, V, S>) {
+        self.inner.push(Cow::Owned(map));
+    }
+
+    /// Adds a borrowed map to the lowest-precedence end of the chain.
+    ///
+    /// The map is only cloned if it is later modified through the chain.
+    pub fn push_borrowed_map(&mut self, map: &'a HashMap<K, V, S>) {
+        self.inner.push(Cow::Borrowed(map));
+    }
+
+    /// Adds an owned map to the highest-precedence end of the chain.
+    pub fn push_front_map(&mut self, map: HashMap<K, V, S>) {
+        self.inner.insert(0, Cow::Owned(map));
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    pub fn pop_map(&mut self) -> Option<Cow<'a, HashMap<K, V, S>>> {
+        self.inner.pop()
+    }
+
+    /// Returns the number of maps in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the map at the given index is still borrowed, or
+    /// `false` if it is owned by the chain, either because it was added as
+    /// an owned map or because it has been cloned to be modified.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn is_borrowed(&self, index: usize) -> Option<bool> {
+        self.inner.get(index).map(|map| match *map {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        })
+    }
+}
+
+impl<'a, K, V, S> CowChainMap<'a, K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().any(|map| map.contains_key(k))
+    }
+
+    /// Returns a reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().filter_map(|map| map.get(k)).next()
+    }
+
+    /// Returns a mutable reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    ///
+    /// If that map is borrowed, it is cloned first.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.inner[index].to_mut().get_mut(k)
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().position(|map| map.contains_key(k))
+    }
+
+    /// Inserts a key-value pair into the highest-precedence map in the chain,
+    /// adding an empty owned map first if the chain is empty.
+    ///
+    /// If the highest-precedence map is borrowed, it is cloned first.
+    /// Returns the value previously associated with the key in that map, if
+    /// any.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V>
+    where
+        S: Default,
+    {
+        if self.inner.is_empty() {
+            self.inner.push(Cow::Owned(HashMap::default()));
+        }
+        self.inner[0].to_mut().insert(k, v)
+    }
+
+    /// Removes a key from the highest-precedence map that contains it,
+    /// returning the value at the key if the key was present in the chain.
+    ///
+    /// If that map is borrowed, it is cloned first. A value for the same key
+    /// in a lower-precedence map will become visible.
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.inner[index].to_mut().remove(k)
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.iter().all(|map| map.is_empty())
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order.
+    ///
+    /// Each key appears once, with its highest-precedence value.
+    pub fn iter(&self) -> CowChainIter<'_, 'a, K, V, S> {
+        CowChainIter {
+            maps: &self.inner,
+            index: 0,
+            current: None,
+        }
+    }
+}
+
+impl<'a, K, V, S> Clone for CowChainMap<'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        CowChainMap {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Default for CowChainMap<'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    fn default() -> Self {
+        CowChainMap::new()
+    }
+}
+
+impl<'a, K, Q, V, S> Index<&Q> for CowChainMap<'a, K, V, S>
+where
+    K: Eq + Hash + Clone + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<'a, K, V, S> Debug for CowChainMap<'a, K, V, S>
+where
+    K: Eq + Hash + Clone + Debug,
+    V: Clone + Debug,
+    S: BuildHasher + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `CowChainMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`CowChainMap`]. See
+/// its documentation for more.
+///
+/// [`iter`]: struct.CowChainMap.html#method.iter
+/// [`CowChainMap`]: struct.CowChainMap.html
+pub struct CowChainIter<'b, 'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    maps: &'b [Cow<'a, HashMap<K, V, S>>],
+    index: usize,
+    current: Option<hash_map::Iter<'b, K, V>>,
+}
+
+impl<'b, 'a, K, V, S> Clone for CowChainIter<'b, 'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        CowChainIter {
+            maps: self.maps,
+            index: self.index,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'b, 'a, K, V, S> Iterator for CowChainIter<'b, 'a, K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    type Item = (&'b K, &'b V);
+
+    fn next(&mut self) -> Option<(&'b K, &'b V)> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let shadowing = &self.maps[..self.index];
+                let entry =
+                    current.find(|&(key, _)| !shadowing.iter().any(|map| map.contains_key(key)));
+                if entry.is_some() {
+                    return entry;
+                }
+                self.index += 1;
+            }
+            let map: &'b HashMap<K, V, S> = self.maps.get(self.index)?;
+            self.current = Some(map.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed_maps_are_only_cloned_when_written() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: CowChainMap<_, _> = CowChainMap::new();
+        chain.push_borrowed_map(&first_map);
+        chain.push_borrowed_map(&second_map);
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.is_borrowed(0), Some(true));
+        assert_eq!(chain.is_borrowed(1), Some(true));
+
+        *chain.get_mut("second").unwrap() += 10;
+        assert_eq!(chain["second"], 12);
+        assert_eq!(chain.is_borrowed(0), Some(true));
+        assert_eq!(chain.is_borrowed(1), Some(false));
+
+        assert_eq!(chain.remove("first"), Some(1));
+        assert_eq!(chain["first"], 2);
+        assert_eq!(chain.is_borrowed(0), Some(false));
+
+        // The source maps are untouched
+        assert_eq!(first_map["first"], 1);
+        assert_eq!(second_map["second"], 2);
+    }
+}
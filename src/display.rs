@@ -0,0 +1,307 @@
+use core::fmt::{self, Debug, Display};
+use core::hash::{BuildHasher, Hash};
+
+use crate::compat::{String, Vec};
+use crate::{ChainMap, LayerId, Link};
+
+impl<K, V, S> ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns an adapter that displays the unified view of the chain, one
+    /// entry per line, along with the map that provides each value.
+    ///
+    /// Each line has the form `key = value  (from source)`, where the source
+    /// is shown as by the `Display` implementation of [`LayerId`]: the name
+    /// of the map, or `layer N` if the map has no name. The lines are sorted
+    /// by the displayed key, so the output is the same each time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut cli = HashMap::new();
+    /// cli.insert("port", 9000);
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("port", 8080);
+    /// defaults.insert("workers", 4);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_named_map("cli", cli);
+    /// chain.push_map(defaults);
+    ///
+    /// assert_eq!(
+    ///     chain.display_resolved().to_string(),
+    ///     "port = 9000  (from cli)\nworkers = 4  (from layer 1)\n"
+    /// );
+    /// ```
+    ///
+    /// [`LayerId`]: struct.LayerId.html
+    pub fn display_resolved(&self) -> DisplayResolved<'_, K, V, S> {
+        DisplayResolved { chain: self }
+    }
+
+    /// Returns an adapter that formats each map in the chain on its own with
+    /// `Debug`, marking the entries that are shadowed by an earlier map.
+    ///
+    /// Each map is shown with its index, its name and whether it is enabled.
+    /// Entries that are hidden by a map earlier in the chain, either because
+    /// that map contains the same key or because it has a tombstone for it,
+    /// have their values wrapped in `Shadowed`. Use the alternate `{:#?}`
+    /// format to print each map and entry on its own line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut cli = HashMap::new();
+    /// cli.insert("port", 9000);
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("port", 8080);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_named_map("cli", cli);
+    /// chain.push_map(defaults);
+    ///
+    /// assert_eq!(
+    ///     format!("{:?}", chain.debug_layers()),
+    ///     "[Layer { index: 0, name: Some(\"cli\"), enabled: true, entries: {\"port\": 9000} }, \
+    ///      Layer { index: 1, name: None, enabled: true, entries: {\"port\": Shadowed(8080)} }]"
+    /// );
+    /// ```
+    pub fn debug_layers(&self) -> DebugLayers<'_, K, V, S> {
+        DebugLayers { chain: self }
+    }
+}
+
+/// An adapter that displays the unified view of a `ChainMap` along with the
+/// source of each value.
+///
+/// This `struct` is created by the [`display_resolved`] method on
+/// [`ChainMap`]. See its documentation for more.
+///
+/// [`display_resolved`]: struct.ChainMap.html#method.display_resolved
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct DisplayResolved<'a, K, V, S> {
+    chain: &'a ChainMap<K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for DisplayResolved<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        DisplayResolved { chain: self.chain }
+    }
+}
+
+impl<'a, K, V, S> Display for DisplayResolved<'a, K, V, S>
+where
+    K: Hash + Eq + Display,
+    V: Display,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.chain.iter();
+        let mut lines: Vec<(String, &V, LayerId<'_>)> = Vec::new();
+        while let Some((key, value)) = iter.next() {
+            // The iterator only moves on to the next map once the current one
+            // is exhausted, so its index is the source of the entry.
+            let index = iter.index;
+            let source = LayerId {
+                index,
+                name: iter.maps[index].name(),
+            };
+            lines.push((format!("{}", key), value, source));
+        }
+        lines.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (key, value, source) in lines {
+            writeln!(f, "{} = {}  (from {})", key, value, source)?;
+        }
+        Ok(())
+    }
+}
+
+/// An adapter that formats each map of a `ChainMap` with `Debug`, marking
+/// shadowed entries.
+///
+/// This `struct` is created by the [`debug_layers`] method on [`ChainMap`].
+/// See its documentation for more.
+///
+/// [`debug_layers`]: struct.ChainMap.html#method.debug_layers
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct DebugLayers<'a, K, V, S> {
+    chain: &'a ChainMap<K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for DebugLayers<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        DebugLayers { chain: self.chain }
+    }
+}
+
+impl<'a, K, V, S> Debug for DebugLayers<'a, K, V, S>
+where
+    K: Hash + Eq + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let links = &self.chain.inner[..];
+        f.debug_list()
+            .entries((0..links.len()).map(|index| DebugLayer { links, index }))
+            .finish()
+    }
+}
+
+/// A single map in a `DebugLayers`, along with the maps before it so that
+/// shadowed entries can be found.
+struct DebugLayer<'a, K, V, S> {
+    links: &'a [Link<K, V, S>],
+    index: usize,
+}
+
+impl<'a, K, V, S> Debug for DebugLayer<'a, K, V, S>
+where
+    K: Hash + Eq + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let link = &self.links[self.index];
+        let shadowing = &self.links[..self.index];
+        let entries = DebugEntries { link, shadowing };
+        f.debug_struct("Layer")
+            .field("index", &self.index)
+            .field("name", &link.name())
+            .field("enabled", &link.enabled)
+            .field("entries", &entries)
+            .finish()
+    }
+}
+
+/// The entries of a single map in a `DebugLayers`.
+struct DebugEntries<'a, K, V, S> {
+    link: &'a Link<K, V, S>,
+    shadowing: &'a [Link<K, V, S>],
+}
+
+impl<'a, K, V, S> Debug for DebugEntries<'a, K, V, S>
+where
+    K: Hash + Eq + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (key, value) in self.link.map.iter() {
+            if self.shadowing.iter().any(|link| link.hides(key)) {
+                map.entry(key, &Shadowed(value));
+            } else {
+                map.entry(key, value);
+            }
+        }
+        map.finish()
+    }
+}
+
+/// Wraps the value of a shadowed entry, so it is shown as `Shadowed(value)`.
+struct Shadowed<'a, V>(&'a V);
+
+impl<'a, V: Debug> Debug for Shadowed<'a, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Shadowed").field(self.0).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::HashMap;
+
+    #[test]
+    fn lines_are_sorted_and_show_source() {
+        let mut first_map = HashMap::new();
+        first_map.insert("b", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("b", 2);
+        second_map.insert("c", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("a", 3);
+
+        let mut chain = ChainMap::new();
+        chain.push_map(first_map);
+        chain.push_named_map("defaults", second_map);
+        chain.push_map(third_map);
+
+        assert_eq!(
+            format!("{}", chain.display_resolved()),
+            "a = 3  (from layer 2)\nb = 1  (from layer 0)\nc = 2  (from defaults)\n"
+        );
+
+        let empty: ChainMap<&str, i32> = ChainMap::new();
+        assert_eq!(format!("{}", empty.display_resolved()), "");
+    }
+
+    #[test]
+    fn debug_layers_marks_shadowed_and_masked_entries() {
+        let mut first_map = HashMap::new();
+        first_map.insert("a", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("a", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("b", 3);
+
+        let mut chain = ChainMap::new();
+        chain.push_map(first_map);
+        chain.push_named_map("defaults", second_map);
+        chain.push_map(third_map);
+        chain.insert_tombstone("b");
+        chain.set_layer_enabled(1, false);
+
+        assert_eq!(
+            format!("{:#?}", chain.debug_layers()),
+            r#"[
+    Layer {
+        index: 0,
+        name: None,
+        enabled: true,
+        entries: {
+            "a": 1,
+        },
+    },
+    Layer {
+        index: 1,
+        name: Some(
+            "defaults",
+        ),
+        enabled: false,
+        entries: {
+            "a": Shadowed(
+                2,
+            ),
+        },
+    },
+    Layer {
+        index: 2,
+        name: None,
+        enabled: true,
+        entries: {
+            "b": Shadowed(
+                3,
+            ),
+        },
+    },
+]"#
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::env;
+
+/// A snapshot of environment variables with a common prefix, for use as a
+/// map in a `ChainMap`.
+///
+/// By default the prefix is removed from the keys and their case is left
+/// unchanged. Variables whose names or values are not valid unicode are
+/// skipped. The variables are read when [`load`] is called, so later changes
+/// to the environment are not reflected in the map.
+///
+/// This `struct` is only available with the `env` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::env;
+/// use chain_map::{ChainMap, EnvLayer, KeyCase};
+///
+/// env::set_var("APP_THEME", "dark");
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme".to_string(), "light".to_string());
+/// defaults.insert("font".to_string(), "mono".to_string());
+///
+/// let env = EnvLayer::with_prefix("APP_").key_case(KeyCase::Lower).load();
+/// let chain: ChainMap<_, _> = vec![env, defaults].into_iter().collect();
+///
+/// assert_eq!(chain["theme"], "dark");
+/// assert_eq!(chain["font"], "mono");
+/// ```
+///
+/// [`load`]: #method.load
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvLayer {
+    prefix: String,
+    strip_prefix: bool,
+    key_case: KeyCase,
+}
+
+/// How the case of environment variable names is changed when they are
+/// loaded by an [`EnvLayer`].
+///
+/// [`EnvLayer`]: struct.EnvLayer.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCase {
+    /// Names are left unchanged. This is the default.
+    Preserve,
+    /// Names are converted to lowercase.
+    Lower,
+    /// Names are converted to uppercase.
+    Upper,
+}
+
+impl Default for KeyCase {
+    fn default() -> Self {
+        KeyCase::Preserve
+    }
+}
+
+impl EnvLayer {
+    /// Creates an `EnvLayer` that loads the environment variables whose
+    /// names start with the given prefix.
+    ///
+    /// An empty prefix matches every variable.
+    pub fn with_prefix<P: Into<String>>(prefix: P) -> Self {
+        EnvLayer {
+            prefix: prefix.into(),
+            strip_prefix: true,
+            key_case: KeyCase::default(),
+        }
+    }
+
+    /// Sets whether the prefix is removed from the names of the variables.
+    ///
+    /// The prefix is removed by default.
+    pub fn strip_prefix(mut self, strip: bool) -> Self {
+        self.strip_prefix = strip;
+        self
+    }
+
+    /// Sets how the case of the names is changed, after the prefix is
+    /// removed.
+    pub fn key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    /// Reads the matching variables from the environment of the current
+    /// process into a map.
+    pub fn load(&self) -> HashMap<String, String> {
+        self.load_from(env::vars_os().filter_map(|(key, value)| {
+            match (key.into_string(), value.into_string()) {
+                (Ok(key), Ok(value)) => Some((key, value)),
+                _ => None,
+            }
+        }))
+    }
+
+    /// Builds a map from the matching variables in the given list of names
+    /// and values, rather than the environment of the current process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::EnvLayer;
+    ///
+    /// let vars = vec![
+    ///     ("APP_PORT".to_string(), "8080".to_string()),
+    ///     ("HOME".to_string(), "/home/user".to_string()),
+    /// ];
+    /// let map = EnvLayer::with_prefix("APP_").load_from(vars);
+    ///
+    /// assert_eq!(map.len(), 1);
+    /// assert_eq!(map["PORT"], "8080");
+    /// ```
+    pub fn load_from<I>(&self, vars: I) -> HashMap<String, String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        vars.into_iter()
+            .filter(|(key, _)| key.starts_with(&self.prefix))
+            .map(|(key, value)| {
+                let key = if self.strip_prefix {
+                    &key[self.prefix.len()..]
+                } else {
+                    &key[..]
+                };
+                let key = match self.key_case {
+                    KeyCase::Preserve => key.to_string(),
+                    KeyCase::Lower => key.to_lowercase(),
+                    KeyCase::Upper => key.to_uppercase(),
+                };
+                (key, value)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Vec<(String, String)> {
+        vec![
+            ("APP_Port".to_string(), "8080".to_string()),
+            ("APP_LOG_LEVEL".to_string(), "debug".to_string()),
+            ("OTHER_PORT".to_string(), "9000".to_string()),
+        ]
+    }
+
+    #[test]
+    fn prefix_stripping_and_case_mapping() {
+        let map = EnvLayer::with_prefix("APP_").load_from(vars());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["Port"], "8080");
+        assert_eq!(map["LOG_LEVEL"], "debug");
+
+        let map = EnvLayer::with_prefix("APP_")
+            .strip_prefix(false)
+            .key_case(KeyCase::Upper)
+            .load_from(vars());
+        assert_eq!(map["APP_PORT"], "8080");
+        assert_eq!(map["APP_LOG_LEVEL"], "debug");
+
+        let map = EnvLayer::with_prefix("")
+            .key_case(KeyCase::Lower)
+            .load_from(vars());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["other_port"], "9000");
+    }
+
+    #[test]
+    fn load_snapshots_environment() {
+        env::set_var("CHAIN_MAP_ENV_LAYER_TEST", "value");
+        let map = EnvLayer::with_prefix("CHAIN_MAP_ENV_LAYER_").load();
+        env::remove_var("CHAIN_MAP_ENV_LAYER_TEST");
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["TEST"], "value");
+    }
+}
@@ -0,0 +1,203 @@
+use core::borrow::Borrow;
+use core::cmp;
+use core::fmt::{self, Debug, Display};
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+
+use crate::compat::Vec;
+use crate::ChainMap;
+
+/// The most suggestions that [`KeyError::suggest`] will record.
+///
+/// [`KeyError::suggest`]: struct.KeyError.html#method.suggest
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The error returned when a key is not present in a `ChainMap`.
+///
+/// This `struct` is returned by the [`try_get`] method on [`ChainMap`]. It
+/// records the missing key, and can be extended with keys from the chain
+/// that are spelled similarly by calling [`suggest`].
+///
+/// [`try_get`]: struct.ChainMap.html#method.try_get
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`suggest`]: #method.suggest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyError<K> {
+    key: K,
+    suggestions: Vec<K>,
+}
+
+impl<K> KeyError<K> {
+    pub(crate) fn new(key: K) -> Self {
+        KeyError {
+            key,
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Returns the key that was not found.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the keys suggested in place of the missing key, closest
+    /// first.
+    ///
+    /// This is empty unless [`suggest`] has been called.
+    ///
+    /// [`suggest`]: #method.suggest
+    pub fn suggestions(&self) -> &[K] {
+        &self.suggestions
+    }
+
+    /// Consumes the error, returning the key that was not found.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Records up to three keys from the unified view of `chain` that are
+    /// spelled similarly to the missing key, closest first.
+    ///
+    /// Keys are compared by the number of single-character edits, or swaps
+    /// of adjacent characters, needed to turn one into the other, and only
+    /// keys within about a third of the length of the missing key are
+    /// suggested. This scans every key in the chain, so it is intended for
+    /// reporting errors rather than for use on a hot path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("port".to_string(), 8080);
+    /// defaults.insert("host".to_string(), 0);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(defaults);
+    ///
+    /// let error = chain
+    ///     .try_get("prot")
+    ///     .map_err(|error| error.suggest(&chain))
+    ///     .unwrap_err();
+    /// assert_eq!(error.suggestions(), ["port".to_string()]);
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "no entry found for key \"prot\", did you mean \"port\"?"
+    /// );
+    /// ```
+    pub fn suggest<V, S>(mut self, chain: &ChainMap<K, V, S>) -> Self
+    where
+        K: Hash + Eq + Clone + Borrow<str>,
+        S: BuildHasher,
+    {
+        let missing = self.key.borrow();
+        let limit = cmp::max(1, missing.chars().count() / 3);
+        let mut candidates: Vec<_> = chain
+            .keys()
+            .filter_map(|key| {
+                let distance = edit_distance(missing, key.borrow());
+                if distance <= limit {
+                    Some((distance, key))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        candidates.sort_by(|&(a_distance, a), &(b_distance, b)| {
+            a_distance
+                .cmp(&b_distance)
+                .then_with(|| Borrow::<str>::borrow(a).cmp(b.borrow()))
+        });
+        self.suggestions = candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, key)| key.clone())
+            .collect();
+        self
+    }
+}
+
+impl<K: Debug> Display for KeyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no entry found for key {:?}", self.key)?;
+        for (index, suggestion) in self.suggestions.iter().enumerate() {
+            let separator = if index == 0 { ", did you mean" } else { " or" };
+            write!(f, "{} {:?}", separator, suggestion)?;
+        }
+        if !self.suggestions.is_empty() {
+            f.write_str("?")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Debug> std::error::Error for KeyError<K> {}
+
+/// Returns the number of single-character insertions, deletions and
+/// substitutions, and swaps of adjacent characters, needed to turn `a` into
+/// `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Only the last two rows of the table are needed to fill in the next.
+    let mut two_back = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 0..a.len() {
+        let mut current = Vec::with_capacity(previous.len());
+        current.push(i + 1);
+        for j in 0..b.len() {
+            let substitution = previous[j] + if a[i] == b[j] { 0 } else { 1 };
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            let mut distance = cmp::min(substitution, cmp::min(insertion, deletion));
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                distance = cmp::min(distance, two_back[j - 1] + 1);
+            }
+            current.push(distance);
+        }
+        two_back = mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::{HashMap, String};
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("port", "port"), 0);
+        assert_eq!(edit_distance("prot", "port"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("host", "hosts"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggestions_are_ordered_by_distance() {
+        let mut map = HashMap::new();
+        for key in &["timeout", "timeouts", "timer", "retries", "time_out"] {
+            map.insert(String::from(*key), 0);
+        }
+        let mut chain = ChainMap::new();
+        chain.push_map(map);
+
+        let error = chain.try_get("timeot").unwrap_err().suggest(&chain);
+        assert_eq!(error.key(), "timeot");
+        assert_eq!(error.suggestions(), ["timeout", "time_out", "timeouts"]);
+        assert_eq!(
+            format!("{}", error),
+            "no entry found for key \"timeot\", did you mean \"timeout\" or \"time_out\" or \"timeouts\"?"
+        );
+
+        let error = chain.try_get("unrelated").unwrap_err().suggest(&chain);
+        assert!(error.suggestions().is_empty());
+        assert_eq!(error.into_key(), "unrelated");
+    }
+}
@@ -0,0 +1,279 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::ops::Index;
+
+use crate::compat::{hash_map, HashMap, RandomState};
+
+/// A chain of exactly `N` [`HashMap`]s with a single view into the values.
+///
+/// The number of maps is part of the type, and the maps are stored in an
+/// array rather than a `Vec`, so a chain with a fixed set of sources, such
+/// as defaults, a config file, the environment and command-line flags, can
+/// be described precisely by its type. Since the length of the array is
+/// known at compile time, the compiler is free to unroll the loop over the
+/// maps in each lookup. The map at index 0 has the highest precedence.
+///
+/// This `struct` is only available with the `const-generics` feature, which
+/// requires Rust 1.59 or later.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "const-generics")]
+/// # {
+/// use std::collections::HashMap;
+/// use chain_map::FixedChainMap;
+///
+/// let mut cli = HashMap::new();
+/// cli.insert("verbose", "true");
+///
+/// let mut env = HashMap::new();
+/// env.insert("port", "9000");
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("port", "8080");
+/// defaults.insert("verbose", "false");
+///
+/// let chain = FixedChainMap::new([cli, env, defaults]);
+///
+/// assert_eq!(chain.get("port"), Some(&"9000"));
+/// assert_eq!(chain["verbose"], "true");
+/// assert_eq!(chain.source_of("port"), Some(1));
+/// # }
+/// ```
+///
+/// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+#[derive(Clone)]
+pub struct FixedChainMap<K, V, const N: usize, S = RandomState> {
+    maps: [HashMap<K, V, S>; N],
+}
+
+impl<K, V, const N: usize, S> FixedChainMap<K, V, N, S> {
+    /// Creates a `FixedChainMap` from an array of maps in precedence order.
+    pub fn new(maps: [HashMap<K, V, S>; N]) -> Self {
+        FixedChainMap { maps }
+    }
+
+    /// Returns the maps in the chain, in precedence order.
+    pub fn maps(&self) -> &[HashMap<K, V, S>; N] {
+        &self.maps
+    }
+
+    /// Returns a mutable reference to the maps in the chain, in precedence
+    /// order.
+    pub fn maps_mut(&mut self) -> &mut [HashMap<K, V, S>; N] {
+        &mut self.maps
+    }
+
+    /// Consumes the `FixedChainMap`, returning the array of maps.
+    pub fn into_maps(self) -> [HashMap<K, V, S>; N] {
+        self.maps
+    }
+
+    /// Returns the number of maps in the chain, which is always `N`.
+    pub fn layer_count(&self) -> usize {
+        N
+    }
+}
+
+impl<K, V, const N: usize, S> FixedChainMap<K, V, N, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.maps.iter().any(|map| map.contains_key(k))
+    }
+
+    /// Returns a reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.maps.iter().find_map(|map| map.get(k))
+    }
+
+    /// Returns a mutable reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.maps.iter_mut().find_map(|map| map.get_mut(k))
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.maps.iter().position(|map| map.contains_key(k))
+    }
+
+    /// Inserts a key-value pair into the map at the given index, returning
+    /// the value previously associated with the key in that map, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `N`.
+    pub fn insert_at(&mut self, index: usize, k: K, v: V) -> Option<V> {
+        self.maps[index].insert(k, v)
+    }
+
+    /// Removes a key from the highest-precedence map that contains it,
+    /// returning the value at the key if the key was present in the chain.
+    ///
+    /// A value for the same key in a lower-precedence map will become
+    /// visible.
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.maps[index].remove(k)
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.maps.iter().all(HashMap::is_empty)
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order.
+    ///
+    /// Each key appears once, with its highest-precedence value.
+    pub fn iter(&self) -> FixedIter<'_, K, V, S> {
+        FixedIter {
+            maps: &self.maps,
+            index: 0,
+            current: None,
+        }
+    }
+}
+
+impl<K, V, const N: usize, S> From<[HashMap<K, V, S>; N]> for FixedChainMap<K, V, N, S> {
+    fn from(maps: [HashMap<K, V, S>; N]) -> Self {
+        FixedChainMap::new(maps)
+    }
+}
+
+impl<K, Q, V, const N: usize, S> Index<&Q> for FixedChainMap<K, V, N, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<K, V, const N: usize, S> Debug for FixedChainMap<K, V, N, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `FixedChainMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`FixedChainMap`]. See
+/// its documentation for more.
+///
+/// [`iter`]: struct.FixedChainMap.html#method.iter
+/// [`FixedChainMap`]: struct.FixedChainMap.html
+pub struct FixedIter<'a, K, V, S> {
+    maps: &'a [HashMap<K, V, S>],
+    index: usize,
+    current: Option<hash_map::Iter<'a, K, V>>,
+}
+
+impl<'a, K, V, S> Clone for FixedIter<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        FixedIter {
+            maps: self.maps,
+            index: self.index,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for FixedIter<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let shadowing = &self.maps[..self.index];
+                let entry =
+                    current.find(|&(key, _)| !shadowing.iter().any(|map| map.contains_key(key)));
+                if entry.is_some() {
+                    return entry;
+                }
+                self.index += 1;
+            }
+            self.current = Some(self.maps.get(self.index)?.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_chain_reads_maps_in_order() {
+        let mut first = HashMap::new();
+        first.insert(1, "first");
+
+        let mut second = HashMap::new();
+        second.insert(1, "second");
+        second.insert(2, "second");
+
+        let mut chain: FixedChainMap<_, _, 3> = [first, second, HashMap::new()].into();
+        assert_eq!(chain.layer_count(), 3);
+        assert_eq!(chain.len(), 2);
+
+        chain.insert_at(2, 3, "third");
+        *chain.get_mut(&2).unwrap() = "updated";
+        assert_eq!(chain[&2], "updated");
+        assert_eq!(chain.source_of(&3), Some(2));
+
+        assert_eq!(chain.remove(&1), Some("first"));
+        assert_eq!(chain[&1], "second");
+
+        let mut entries: Vec<_> = chain.iter().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![(&1, &"second"), (&2, &"updated"), (&3, &"third")]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::ops::Index;
+
+use crate::compat::{hash_map, HashMap, HashSet, RandomState};
+
+use super::ChainMap;
+
+/// A read-only `ChainMap` with constant-time lookups.
+///
+/// This `struct` is created by the [`freeze`] method on [`ChainMap`]. When it
+/// is created, the map that provides the value for each key is recorded in an
+/// index, so lookups take a single hash lookup in the index and one in the
+/// providing map, regardless of the number of maps in the chain. The chain
+/// itself can still be read through [`chain`], or recovered with
+/// [`into_chain`] to make further changes.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::ChainMap;
+///
+/// let mut overrides = HashMap::new();
+/// overrides.insert("theme", "dark");
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme", "light");
+/// defaults.insert("font", "mono");
+///
+/// let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+/// let frozen = chain.freeze();
+///
+/// assert_eq!(frozen.get("theme"), Some(&"dark"));
+/// assert_eq!(frozen["font"], "mono");
+/// assert_eq!(frozen.source_of("font"), Some(1));
+/// assert_eq!(frozen.len(), 2);
+/// ```
+///
+/// [`freeze`]: struct.ChainMap.html#method.freeze
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`chain`]: #method.chain
+/// [`into_chain`]: #method.into_chain
+#[derive(Clone)]
+pub struct FrozenChainMap<K, V, S = RandomState> {
+    chain: ChainMap<K, V, S>,
+    index: HashMap<K, usize, S>,
+}
+
+impl<K, V, S> FrozenChainMap<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    S: BuildHasher + Default,
+{
+    pub(crate) fn new(chain: ChainMap<K, V, S>) -> Self {
+        let mut index = HashMap::default();
+        {
+            let mut masked = HashSet::new();
+            for (slot, link) in chain.inner.iter().enumerate() {
+                if !link.enabled {
+                    continue;
+                }
+                for key in link.map.keys() {
+                    if !masked.contains(key) && !index.contains_key(key) {
+                        index.insert(key.clone(), slot);
+                    }
+                }
+                if let Some(ref tombstones) = link.tombstones {
+                    masked.extend(tombstones);
+                }
+            }
+        }
+
+        FrozenChainMap { chain, index }
+    }
+}
+
+impl<K, V, S> FrozenChainMap<K, V, S> {
+    /// Returns a reference to the underlying chain.
+    pub fn chain(&self) -> &ChainMap<K, V, S> {
+        &self.chain
+    }
+
+    /// Consumes the `FrozenChainMap`, returning the underlying chain.
+    pub fn into_chain(self) -> ChainMap<K, V, S> {
+        self.chain
+    }
+}
+
+impl<K, V, S> FrozenChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if the chain contains a value for the given key.
+    ///
+    /// See [`ChainMap::contains_key`] for more.
+    ///
+    /// [`ChainMap::contains_key`]: struct.ChainMap.html#method.contains_key
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.contains_key(k)
+    }
+
+    /// Returns a reference to the highest-precedence value associated with
+    /// the given key.
+    ///
+    /// See [`ChainMap::get`] for more.
+    ///
+    /// [`ChainMap::get`]: struct.ChainMap.html#method.get
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = *self.index.get(k)?;
+        self.chain.inner[slot].map.get(k)
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key.
+    ///
+    /// See [`ChainMap::source_of`] for more.
+    ///
+    /// [`ChainMap::source_of`]: struct.ChainMap.html#method.source_of
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(k).cloned()
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order.
+    pub fn iter(&self) -> FrozenIter<'_, K, V, S> {
+        FrozenIter {
+            chain: &self.chain,
+            index: self.index.iter(),
+        }
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for FrozenChainMap<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<K, V, S> Debug for FrozenChainMap<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `FrozenChainMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`FrozenChainMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: struct.FrozenChainMap.html#method.iter
+/// [`FrozenChainMap`]: struct.FrozenChainMap.html
+pub struct FrozenIter<'a, K, V, S> {
+    chain: &'a ChainMap<K, V, S>,
+    index: hash_map::Iter<'a, K, usize>,
+}
+
+impl<'a, K, V, S> Clone for FrozenIter<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        FrozenIter {
+            chain: self.chain,
+            index: self.index.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for FrozenIter<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let chain = self.chain;
+        self.index
+            .next()
+            .map(|(key, &slot)| (key, &chain.inner[slot].map[key]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.index.size_hint()
+    }
+}
+
+impl<'a, K, V, S> ExactSizeIterator for FrozenIter<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::Vec;
+
+    #[test]
+    fn freeze_matches_chain_lookups() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("shared", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("shared", 2);
+        second_map.insert("deleted", 2);
+        second_map.insert("hidden", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("hidden", 3);
+
+        let mut chain: ChainMap<_, _> =
+            vec![first_map, second_map, third_map].into_iter().collect();
+        chain.insert_tombstone("deleted");
+        chain.set_layer_enabled(1, false);
+
+        let frozen = chain.clone().freeze();
+
+        assert_eq!(frozen.len(), chain.len());
+        for (key, value) in chain.iter() {
+            assert_eq!(frozen.get(key), Some(value));
+            assert_eq!(frozen.source_of(key), chain.source_of(key));
+        }
+        assert!(!frozen.contains_key("deleted"));
+        assert_eq!(frozen["hidden"], 3);
+
+        let mut entries: Vec<_> = frozen.iter().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![(&"first", &1), (&"hidden", &3), (&"shared", &1)]
+        );
+
+        assert_eq!(frozen.into_chain(), chain);
+    }
+}
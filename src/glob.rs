@@ -0,0 +1,197 @@
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::iter;
+
+use super::{ChainMap, Iter, LayerId};
+
+impl<K, V, S> ChainMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<str>,
+    S: BuildHasher,
+{
+    /// An iterator visiting the entries in the unified view whose keys match
+    /// a glob pattern, along with the [`LayerId`] of the map that provides
+    /// each value.
+    ///
+    /// Keys are treated as a sequence of segments separated by `.`. In the
+    /// pattern, `*` matches any run of characters within a single segment,
+    /// `?` matches any one character other than `.`, and `**` matches any
+    /// run of characters, including `.`. Every other character must match
+    /// exactly. Each key appears once, with its highest-precedence value,
+    /// and the entries are visited in arbitrary order. This scans every
+    /// entry in the chain, so it runs in the same time as [`iter`].
+    ///
+    /// This method is only available with the `glob` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut cli = HashMap::new();
+    /// cli.insert("log.http.level", "debug");
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("log.http.level", "info");
+    /// defaults.insert("log.db.level", "warn");
+    /// defaults.insert("log.db.pool.level", "error");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_named_map("cli", cli);
+    /// chain.push_named_map("defaults", defaults);
+    ///
+    /// let mut levels: Vec<_> = chain
+    ///     .query("log.*.level")
+    ///     .map(|(key, value, source)| (*key, *value, source.to_string()))
+    ///     .collect();
+    /// levels.sort();
+    /// assert_eq!(
+    ///     levels,
+    ///     vec![
+    ///         ("log.db.level", "warn", "defaults".to_string()),
+    ///         ("log.http.level", "debug", "cli".to_string()),
+    ///     ]
+    /// );
+    ///
+    /// assert_eq!(chain.query("log.**.level").count(), 3);
+    /// ```
+    ///
+    /// [`LayerId`]: struct.LayerId.html
+    /// [`iter`]: #method.iter
+    pub fn query<'a, 'p>(&'a self, pattern: &'p str) -> Query<'a, 'p, K, V, S> {
+        Query {
+            inner: self.iter(),
+            pattern,
+        }
+    }
+}
+
+/// An iterator over the entries of a `ChainMap` whose keys match a glob
+/// pattern.
+///
+/// This `struct` is created by the [`query`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`query`]: struct.ChainMap.html#method.query
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Query<'a, 'p, K, V, S> {
+    inner: Iter<'a, K, V, S>,
+    pattern: &'p str,
+}
+
+impl<'a, 'p, K, V, S> Clone for Query<'a, 'p, K, V, S> {
+    fn clone(&self) -> Self {
+        Query {
+            inner: self.inner.clone(),
+            pattern: self.pattern,
+        }
+    }
+}
+
+impl<'a, 'p, K, V, S> Iterator for Query<'a, 'p, K, V, S>
+where
+    K: Hash + Eq + Borrow<str>,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V, LayerId<'a>);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V, LayerId<'a>)> {
+        let pattern = self.pattern;
+        let (key, value) = self
+            .inner
+            .find(|&(key, _)| glob_match(pattern, key.borrow()))?;
+        // The iterator only moves on to the next map once the current one is
+        // exhausted, so its index is the source of the entry just returned.
+        let index = self.inner.index;
+        let source = LayerId {
+            index,
+            name: self.inner.maps[index].name(),
+        };
+        Some((key, value, source))
+    }
+}
+
+fn glob_match(pattern: &str, key: &str) -> bool {
+    let mut chars = pattern.chars();
+    match chars.next() {
+        None => key.is_empty(),
+        Some('*') => {
+            let rest = chars.as_str();
+            if rest.starts_with('*') {
+                let rest = &rest[1..];
+                key.char_indices()
+                    .map(|(index, _)| index)
+                    .chain(iter::once(key.len()))
+                    .any(|index| glob_match(rest, &key[index..]))
+            } else {
+                let segment_end = key.find('.').unwrap_or(key.len());
+                key[..segment_end]
+                    .char_indices()
+                    .map(|(index, _)| index)
+                    .chain(iter::once(segment_end))
+                    .any(|index| glob_match(rest, &key[index..]))
+            }
+        }
+        Some('?') => match key.chars().next() {
+            Some(c) if c != '.' => glob_match(chars.as_str(), &key[c.len_utf8()..]),
+            _ => false,
+        },
+        Some(expected) => match key.chars().next() {
+            Some(c) if c == expected => glob_match(chars.as_str(), &key[c.len_utf8()..]),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::HashMap;
+
+    #[test]
+    fn wildcards_respect_segments() {
+        assert!(glob_match("log.*.level", "log.http.level"));
+        assert!(glob_match("log.*.level", "log..level"));
+        assert!(!glob_match("log.*.level", "log.db.pool.level"));
+        assert!(glob_match("log.**.level", "log.db.pool.level"));
+        assert!(glob_match("**", "any.thing"));
+        assert!(glob_match("db.?ost", "db.host"));
+        assert!(!glob_match("db?host", "db.host"));
+        assert!(glob_match("*.port", "db.port"));
+        assert!(!glob_match("*", "db.port"));
+        assert!(glob_match("caf\u{e9}.*", "caf\u{e9}.m\u{e9}nu"));
+        assert!(!glob_match("log.level", "log.levels"));
+    }
+
+    #[test]
+    fn query_reports_source_of_resolved_values() {
+        let mut first_map = HashMap::new();
+        first_map.insert("log.http.level", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("log.http.level", 2);
+        second_map.insert("log.db.level", 2);
+        second_map.insert("net.port", 2);
+
+        let mut chain = ChainMap::new();
+        chain.push_map(first_map);
+        chain.push_named_map("defaults", second_map);
+
+        let mut results: Vec<_> = chain
+            .query("log.*.level")
+            .map(|(key, value, source)| (*key, *value, source.index(), source.name()))
+            .collect();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                ("log.db.level", 2, 1, Some("defaults")),
+                ("log.http.level", 1, 0, None),
+            ]
+        );
+
+        assert_eq!(chain.query("net.port").count(), 1);
+        assert_eq!(chain.query("missing.*").next(), None);
+    }
+}
@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use serde_json::Value;
+
+use super::ChainMap;
+
+impl ChainMap<String, Value> {
+    /// Creates a chain from a list of JSON objects, with one map for each
+    /// object.
+    ///
+    /// The objects are given in precedence order, so the first object has the
+    /// highest precedence. Only the top level of each object becomes a map in
+    /// the chain; nested objects are stored as values and are not merged.
+    /// This method is only available with the `serde_json` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`JsonLayerError`] if any of the values is not a JSON object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    /// use serde_json::json;
+    ///
+    /// let chain = ChainMap::from_json_layers(vec![
+    ///     json!({ "theme": "dark" }),
+    ///     json!({ "theme": "light", "font": "mono" }),
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert_eq!(chain["theme"], "dark");
+    /// assert_eq!(chain["font"], "mono");
+    ///
+    /// let error = ChainMap::from_json_layers(vec![json!({}), json!([1, 2])]).unwrap_err();
+    /// assert_eq!(error.index(), 1);
+    /// ```
+    ///
+    /// [`JsonLayerError`]: struct.JsonLayerError.html
+    pub fn from_json_layers(values: Vec<Value>) -> Result<Self, JsonLayerError> {
+        let mut chain = ChainMap::with_capacity(values.len());
+        for (index, value) in values.into_iter().enumerate() {
+            match value {
+                Value::Object(object) => chain.push_map(object.into_iter().collect()),
+                other => {
+                    return Err(JsonLayerError {
+                        index,
+                        found: kind_of(&other),
+                    })
+                }
+            }
+        }
+        Ok(chain)
+    }
+}
+
+fn kind_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// The error returned when a JSON value used as a layer is not an object.
+///
+/// This `struct` is returned by the [`from_json_layers`] method on
+/// [`ChainMap`].
+///
+/// [`from_json_layers`]: struct.ChainMap.html#method.from_json_layers
+/// [`ChainMap`]: struct.ChainMap.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonLayerError {
+    index: usize,
+    found: &'static str,
+}
+
+impl JsonLayerError {
+    /// Returns the index of the value that is not an object.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Display for JsonLayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a JSON object for layer {}, found {}",
+            self.index, self.found
+        )
+    }
+}
+
+impl Error for JsonLayerError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn objects_become_layers_in_order() {
+        let chain = ChainMap::from_json_layers(vec![
+            json!({ "first": 1, "nested": { "a": 1 } }),
+            json!({ "first": 2, "second": 2, "nested": { "b": 2 } }),
+        ])
+        .unwrap();
+
+        assert_eq!(chain.layer_sizes(), vec![2, 3]);
+        assert_eq!(chain["first"], 1);
+        assert_eq!(chain["second"], 2);
+        assert_eq!(chain["nested"], json!({ "a": 1 }));
+    }
+
+    #[test]
+    fn non_object_values_are_errors() {
+        let error =
+            ChainMap::from_json_layers(vec![json!({}), json!({}), json!("text")]).unwrap_err();
+
+        assert_eq!(error.index(), 2);
+        assert_eq!(
+            error.to_string(),
+            "expected a JSON object for layer 2, found a string"
+        );
+        assert!(ChainMap::from_json_layers(vec![json!(null)]).is_err());
+    }
+}
@@ -10,9 +10,44 @@
 //!
 //! # Precedence
 //!
-//! Maps added to the [`ChainMap`] earlier have precedence over those added
-//! later. So the first map added to the chain will have the highest
-//! precedence, while the most recent map added will have the lowest.
+//! By default, maps added to the [`ChainMap`] earlier have precedence over
+//! those added later. So the first map added to the chain will have the
+//! highest precedence, while the most recent map added will have the lowest.
+//!
+//! A chain created with [`Precedence::LastWins`] reverses this, so that each
+//! newly added map takes precedence over all of the existing maps. In either
+//! case, maps are indexed in precedence order, so the map at index 0 always
+//! has the highest precedence.
+//!
+//! # Other Map Types
+//!
+//! The maps in a [`ChainMap`] are always [`HashMap`]s that share a single
+//! hasher.
+//!
+//! A [`FixedChainMap`] holds a number of maps that is fixed by its type, in
+//! an array rather than a `Vec`.
+//!
+//! A [`ChainBTreeMap`] chains [`BTreeMap`]s instead, so the unified view can
+//! be iterated in key order and scanned with range queries.
+//!
+//! A [`ChainSet`] chains [`HashSet`]s with the same precedence semantics,
+//! providing a view of their union.
+//!
+//! A [`ChainMapRef`] chains references to [`HashMap`]s owned elsewhere, so
+//! they can be viewed through a chain without being cloned or moved.
+//! A [`CowChainMap`] can mix borrowed maps with owned ones, and only clones
+//! a borrowed map when it is modified through the chain.
+//!
+//! A [`SharedChainMap`] holds its maps in an `Arc`, so cloning the chain only
+//! copies a pointer per map, and a map shared between chains is only cloned
+//! when one of them modifies it. An [`RcChainMap`] does the same with an `Rc`
+//! for chains that are only used from a single thread.
+//!
+//! A [`SyncChainMap`] wraps a [`ChainMap`] in a read-write lock, so a single
+//! chain can be read and modified from many threads at once. For chains that
+//! are read far more often than they change, a [`SwapChainMap`] publishes a
+//! new version of a [`SharedChainMap`] on each change, so reads never wait
+//! for a lock and a change only copies the map it modifies.
 //!
 //! # Performance
 //!
@@ -21,6 +56,63 @@
 //! the chain. As a result, this should only be used for cases where the number
 //! of reads is low compared to the number of elements in each map.
 //!
+//! For chains where most lookups are for keys that aren't present, calling
+//! [`ChainMap::enable_bloom_filters`] builds a Bloom filter over the keys in
+//! each map, so lookups can skip most maps without hashing into them.
+//!
+//! # Optional Features
+//!
+//! - `std` (enabled by default): Backs each map in the chain with the standard
+//!   library's [`HashMap`]. Without it, the crate is `no_std` and only needs
+//!   `alloc`, but the `hashbrown` feature must be enabled instead.
+//! - `hashbrown`: Backs each map in the chain with [`hashbrown::HashMap`]
+//!   when the `std` feature is disabled, for use on targets without the
+//!   standard library. The `env` and `watch` features turn `std` back on,
+//!   and the `arc-swap`, `rayon`, `serde`, `serde_json` and `toml` features
+//!   require it.
+//!   This feature requires the minimum Rust version supported by
+//!   `hashbrown`.
+//! - `arc-swap`: Enables [`SwapChainMap`], which publishes each version of a
+//!   chain atomically, so it can be read from many threads without locking.
+//!   This feature requires the minimum Rust version supported by `arc-swap`.
+//! - `const-generics`: Enables [`FixedChainMap`], which holds a fixed number
+//!   of maps in an array, [`ChainMap::get_many`], which looks up an array of
+//!   keys at once, and the conversion of an array of maps into a
+//!   [`ChainMap`] with `From`. This feature requires Rust 1.59 or later.
+//! - `env`: Enables [`EnvLayer`], which loads environment variables with a
+//!   common prefix into a map.
+//! - `glob`: Enables [`ChainMap::query`], which finds the entries in the
+//!   unified view whose string keys match a glob pattern such as
+//!   `log.*.level`, along with the map that provides each value.
+//! - `rayon`: Enables [`ChainMap::par_get_many`], which looks up a batch of
+//!   keys in parallel using [`rayon`], parallel iterators over the unified
+//!   view of the chain, such as [`ChainMap::par_iter`], and collecting a
+//!   chain from a parallel iterator of maps. This feature requires the
+//!   minimum Rust version supported by `rayon`, which is newer than the
+//!   minimum for the rest of the crate.
+//! - `smallvec`: Stores the first few maps of each chain inline with
+//!   [`SmallVec`], so creating a short chain doesn't allocate a list of
+//!   maps. This feature requires the minimum Rust version supported by
+//!   `smallvec`.
+//! - `serde`: Implements [`Serialize`] and [`Deserialize`] for [`ChainMap`],
+//!   as its precedence and the sequence of its maps in precedence order.
+//!   Each map is stored along with its name, priority, tombstones and
+//!   enabled state, so a chain is restored exactly as it was, apart from
+//!   any Bloom filters, which must be enabled again. To serialize only the
+//!   resolved entries as a single map, use
+//!   [`ChainMap::serialize_resolved`] or the [`resolved`] module. This
+//!   feature requires the minimum Rust version supported by `serde`.
+//! - `serde_json`: Enables [`ChainMap::from_json_layers`], which builds a
+//!   chain from a list of JSON objects. This feature requires the minimum
+//!   Rust version supported by `serde_json`.
+//! - `toml`: Enables the [`toml_layer`] module, which loads TOML documents
+//!   into maps, flattening nested tables into dotted keys. This feature
+//!   requires the minimum Rust version supported by `toml`.
+//! - `watch`: Enables [`WatchedChainMap`], which reloads one of the maps in a
+//!   chain from a file whenever the file changes. The file is polled from a
+//!   background thread rather than watched with operating system
+//!   notifications.
+//!
 //! # Examples
 //!
 //! ```
@@ -48,21 +140,425 @@
 //! ```
 //!
 //! [`ChainMap`]: struct.ChainMap.html
+//! [`Precedence::LastWins`]: enum.Precedence.html#variant.LastWins
+//! [`FixedChainMap`]: struct.FixedChainMap.html
+//! [`ChainBTreeMap`]: struct.ChainBTreeMap.html
+//! [`ChainSet`]: struct.ChainSet.html
+//! [`ChainMapRef`]: struct.ChainMapRef.html
+//! [`CowChainMap`]: struct.CowChainMap.html
+//! [`SharedChainMap`]: struct.SharedChainMap.html
+//! [`RcChainMap`]: type.RcChainMap.html
+//! [`SyncChainMap`]: struct.SyncChainMap.html
+//! [`SwapChainMap`]: struct.SwapChainMap.html
+//! [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
+//! [`BTreeMap`]: https://doc.rust-lang.org/std/collections/struct.BTreeMap.html
+//! [`EnvLayer`]: struct.EnvLayer.html
+//! [`ChainMap::query`]: struct.ChainMap.html#method.query
+//! [`ChainMap::get_many`]: struct.ChainMap.html#method.get_many
+//! [`ChainMap::enable_bloom_filters`]: struct.ChainMap.html#method.enable_bloom_filters
+//! [`ChainMap::par_get_many`]: struct.ChainMap.html#method.par_get_many
+//! [`ChainMap::par_iter`]: struct.ChainMap.html#method.par_iter
+//! [`rayon`]: https://docs.rs/rayon
+//! [`SmallVec`]: https://docs.rs/smallvec/1/smallvec/struct.SmallVec.html
+//! [`Serialize`]: https://docs.rs/serde/1/serde/trait.Serialize.html
+//! [`Deserialize`]: https://docs.rs/serde/1/serde/trait.Deserialize.html
+//! [`ChainMap::serialize_resolved`]: struct.ChainMap.html#method.serialize_resolved
+//! [`resolved`]: resolved/index.html
+//! [`ChainMap::from_json_layers`]: struct.ChainMap.html#method.from_json_layers
+//! [`toml_layer`]: toml_layer/index.html
+//! [`WatchedChainMap`]: struct.WatchedChainMap.html
 //! [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+//! [`hashbrown::HashMap`]: https://docs.rs/hashbrown/0.17/hashbrown/struct.HashMap.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(all(not(feature = "std"), not(feature = "hashbrown")))]
+compile_error!("the `hashbrown` feature is required when the `std` feature is disabled");
+#[cfg(all(
+    not(feature = "std"),
+    any(
+        feature = "arc-swap",
+        feature = "rayon",
+        feature = "serde",
+        feature = "serde_json",
+        feature = "toml"
+    )
+))]
+compile_error!(
+    "the `arc-swap`, `rayon`, `serde`, `serde_json` and `toml` features require the `std` feature"
+);
+
+use core::borrow::Borrow;
+use core::fmt::{self, Debug, Display};
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::iter::{self, Enumerate, FromIterator};
+use core::marker::PhantomData;
+use core::ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds};
+use core::slice;
 
-use std::borrow::Borrow;
-use std::collections::hash_map::RandomState;
-use std::collections::HashMap;
-use std::fmt::{self, Debug};
-use std::hash::{BuildHasher, Hash};
-use std::iter::FromIterator;
-use std::ops::Index;
+use crate::bloom::{hash_key, BloomFilter, Probe};
+#[cfg(not(feature = "smallvec"))]
+use crate::compat::vec;
+use crate::compat::{hash_map, Arc, Cow, HashMap, HashSet, RandomState, String, ToOwned, Vec};
+
+/// Expands to the items it is given.
+///
+/// The items are only parsed if the invocation is compiled, so items that
+/// need a newer compiler can be written inside it behind a feature flag.
+#[cfg(feature = "const-generics")]
+macro_rules! const_generics {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+mod bloom;
+mod borrowed;
+mod btree;
+mod builder;
+mod caching;
+mod compat;
+mod cow;
+mod display;
+#[cfg(feature = "env")]
+mod env;
+mod error;
+#[cfg(feature = "const-generics")]
+mod fixed;
+mod frozen;
+#[cfg(feature = "glob")]
+mod glob;
+#[cfg(feature = "serde_json")]
+mod json;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "serde")]
+pub mod resolved;
+mod resolver;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod set;
+mod shared;
+#[cfg(feature = "arc-swap")]
+mod swap;
+#[cfg(feature = "std")]
+mod sync;
+#[cfg(feature = "toml")]
+pub mod toml_layer;
+mod view;
+#[cfg(feature = "watch")]
+mod watch;
+
+pub use crate::borrowed::{ChainMapRef, ChainMapRefIter};
+pub use crate::btree::{BTreeIter, ChainBTreeMap};
+pub use crate::builder::ChainMapBuilder;
+pub use crate::caching::{CacheStats, CachingChainMap};
+pub use crate::cow::{CowChainIter, CowChainMap};
+pub use crate::display::{DebugLayers, DisplayResolved};
+#[cfg(feature = "env")]
+pub use crate::env::{EnvLayer, KeyCase};
+pub use crate::error::KeyError;
+#[cfg(feature = "const-generics")]
+pub use crate::fixed::{FixedChainMap, FixedIter};
+pub use crate::frozen::{FrozenChainMap, FrozenIter};
+#[cfg(feature = "glob")]
+pub use crate::glob::Query;
+#[cfg(feature = "serde_json")]
+pub use crate::json::JsonLayerError;
+#[cfg(feature = "rayon")]
+pub use crate::par::{ParIter, ParKeys, ParValues};
+pub use crate::resolver::ResolvingChainMap;
+#[cfg(feature = "serde")]
+pub use crate::serde_impl::SerializeResolved;
+pub use crate::set::{ChainSet, ChainSetIter};
+pub use crate::shared::{RcChainMap, SharedChainMap, SharedIter, SharedPtr};
+#[cfg(feature = "arc-swap")]
+pub use crate::swap::SwapChainMap;
+#[cfg(feature = "std")]
+pub use crate::sync::SyncChainMap;
+pub use crate::view::{ChainView, FilterIter, FilterView, MapIter, MapView};
+#[cfg(feature = "watch")]
+pub use crate::watch::WatchedChainMap;
 
 #[derive(Clone)]
 /// The `ChainMap` type. See [the module level documentation](index.html) for
 /// more.
 pub struct ChainMap<K, V, S = RandomState> {
-    inner: Vec<HashMap<K, V, S>>,
+    inner: Links<K, V, S>,
+    precedence: Precedence,
+}
+
+/// The storage for the maps in a `ChainMap`.
+///
+/// With the `smallvec` feature, the first few maps are stored inline, so
+/// short chains don't need a separate allocation for the list of maps.
+#[cfg(not(feature = "smallvec"))]
+type Links<K, V, S> = Vec<Link<K, V, S>>;
+#[cfg(feature = "smallvec")]
+type Links<K, V, S> = smallvec::SmallVec<[Link<K, V, S>; INLINE_LINKS]>;
+
+#[cfg(not(feature = "smallvec"))]
+type LinksIntoIter<K, V, S> = vec::IntoIter<Link<K, V, S>>;
+#[cfg(feature = "smallvec")]
+type LinksIntoIter<K, V, S> = smallvec::IntoIter<[Link<K, V, S>; INLINE_LINKS]>;
+
+/// The number of maps stored inline with the `smallvec` feature.
+#[cfg(feature = "smallvec")]
+const INLINE_LINKS: usize = 4;
+
+/// Returns the links that may provide a value for the given key: every link
+/// up to and including the first one with a tombstone for the key.
+fn visible_links<'a, K, V, S, Q>(links: &'a [Link<K, V, S>], k: &Q) -> &'a [Link<K, V, S>]
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    let end = links
+        .iter()
+        .position(|link| link.enabled && link.masks(k))
+        .map_or(links.len(), |index| index + 1);
+    &links[..end]
+}
+
+/// Determines where newly added maps are placed in a `ChainMap`.
+///
+/// See [the module level documentation](index.html#precedence) for more.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Precedence {
+    /// Maps added earlier take precedence over maps added later. This is the
+    /// default.
+    FirstWins,
+    /// Maps added later take precedence over maps added earlier.
+    LastWins,
+}
+
+impl Default for Precedence {
+    fn default() -> Self {
+        Precedence::FirstWins
+    }
+}
+
+/// A single map in a `ChainMap`, along with the name it was given when it
+/// was added to the chain, if any, its priority, the keys it deletes from
+/// lower-precedence maps, whether it is enabled and a Bloom filter over its
+/// keys, if filters have been enabled.
+#[derive(Clone)]
+struct Link<K, V, S> {
+    map: HashMap<K, V, S>,
+    name: Option<String>,
+    priority: i32,
+    tombstones: Option<HashSet<K>>,
+    enabled: bool,
+    bloom: Option<BloomFilter>,
+}
+
+impl<K, V, S> Link<K, V, S> {
+    fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+}
+
+impl<K, V, S> Link<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if this map has a tombstone for the key, hiding it in
+    /// all lower-precedence maps.
+    fn masks<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tombstones
+            .as_ref()
+            .map_or(false, |tombstones| tombstones.contains(k))
+    }
+
+    /// Returns the value for the key in this map, if the map is enabled.
+    fn lookup<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.enabled {
+            self.map.get(k)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the stored key and the value for the key in this map.
+    #[cfg(feature = "std")]
+    fn key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // `HashMap::get_key_value` is newer than the minimum supported Rust
+        // version, so the map is scanned for the stored key instead.
+        if self.map.contains_key(k) {
+            self.map.iter().find(|&(key, _)| key.borrow() == k)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the stored key and the value for the key in this map.
+    #[cfg(not(feature = "std"))]
+    fn key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(k)
+    }
+
+    /// Returns the stored key and a mutable reference to the value for the
+    /// key in this map.
+    #[cfg(feature = "std")]
+    fn key_value_mut<Q>(&mut self, k: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // The standard library's `HashMap` can't lend out its key alongside a
+        // mutable value, so the map is scanned for the stored key instead.
+        self.map.iter_mut().find(|&(key, _)| key.borrow() == k)
+    }
+
+    /// Returns the stored key and a mutable reference to the value for the
+    /// key in this map.
+    #[cfg(not(feature = "std"))]
+    fn key_value_mut<Q>(&mut self, k: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value_mut(k)
+    }
+
+    /// Returns `true` if this map is enabled and contains the key.
+    fn provides<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.enabled && self.map.contains_key(k)
+    }
+
+    /// Returns `true` if this map is enabled and either contains or masks the
+    /// key, so any entries for the key in lower-precedence maps are not
+    /// visible.
+    fn hides<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.enabled && (self.map.contains_key(k) || self.masks(k))
+    }
+
+    /// Returns `false` if this map's Bloom filter shows that it does not
+    /// contain the key, so the map itself doesn't need to be checked.
+    fn may_contain<Q>(&self, probe: &Probe<'_, Q>) -> bool
+    where
+        Q: Hash + ?Sized,
+    {
+        self.bloom
+            .as_ref()
+            .map_or(true, |bloom| bloom.might_contain(probe.hash()))
+    }
+
+    /// Rebuilds the Bloom filter from the current keys in the map.
+    fn build_bloom(&mut self) {
+        self.bloom = Some(BloomFilter::build(self.map.keys()));
+    }
+
+    /// Records a key that is about to be added to the map in its Bloom
+    /// filter, if it has one, rebuilding the filter once it is full.
+    fn note_insert(&mut self, k: &K) {
+        let full = match self.bloom {
+            Some(ref bloom) => bloom.is_full(),
+            None => return,
+        };
+        if full {
+            self.build_bloom();
+        }
+        if let Some(ref mut bloom) = self.bloom {
+            bloom.insert(bloom::hash_key(k));
+        }
+    }
+}
+
+impl<K, V, S> From<HashMap<K, V, S>> for Link<K, V, S> {
+    fn from(map: HashMap<K, V, S>) -> Self {
+        Link {
+            map,
+            name: None,
+            priority: 0,
+            tombstones: None,
+            enabled: true,
+            bloom: None,
+        }
+    }
+}
+
+impl<K, V, S> PartialEq for Link<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Link<K, V, S>) -> bool {
+        let tombstones_eq = match (&self.tombstones, &other.tombstones) {
+            (Some(a), Some(b)) => a == b,
+            (Some(tombstones), None) | (None, Some(tombstones)) => tombstones.is_empty(),
+            (None, None) => true,
+        };
+
+        self.name == other.name
+            && self.priority == other.priority
+            && self.enabled == other.enabled
+            && tombstones_eq
+            && self.map == other.map
+    }
+}
+
+impl<K, V, S> Hash for Link<K, V, S>
+where
+    K: Eq + Hash,
+    V: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // A missing set of tombstones compares equal to an empty one, so both
+        // hash the same.
+        let tombstones = self.tombstones.iter().flatten();
+
+        self.name.hash(state);
+        self.priority.hash(state);
+        self.enabled.hash(state);
+        tombstones.clone().count().hash(state);
+        unordered_hash(tombstones).hash(state);
+        self.map.len().hash(state);
+        unordered_hash(self.map.iter()).hash(state);
+    }
+}
+
+/// Combines the hashes of the items in a way that doesn't depend on their
+/// order, for hashing the contents of maps and sets.
+fn unordered_hash<I>(items: I) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    items
+        .into_iter()
+        .fold(0, |sum: u64, item| sum.wrapping_add(hash_key(&item)))
 }
 
 impl<K, V, S> ChainMap<K, V, S> {
@@ -98,15 +594,55 @@ impl<K, V, S> ChainMap<K, V, S> {
     /// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
     pub fn with_capacity(capacity: usize) -> Self {
         ChainMap {
-            inner: Vec::with_capacity(capacity),
+            inner: Links::with_capacity(capacity),
+            precedence: Precedence::default(),
         }
     }
 
-    /// Appends a map to the lowest-precedence end of the chain
+    /// Creates an empty `ChainMap` that adds new maps according to the given
+    /// precedence.
     ///
-    /// # Panics
+    /// # Examples
     ///
-    /// Panics if the number of maps in the chain overflows a [`usize`].
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::{ChainMap, Precedence};
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("theme", "light");
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert("theme", "dark");
+    ///
+    /// let mut chain = ChainMap::with_precedence(Precedence::LastWins);
+    /// chain.push_map(defaults);
+    /// chain.push_map(overrides);
+    ///
+    /// assert_eq!(chain["theme"], "dark");
+    /// ```
+    pub fn with_precedence(precedence: Precedence) -> Self {
+        ChainMap {
+            inner: Links::new(),
+            precedence,
+        }
+    }
+
+    /// Returns the precedence used when adding new maps to the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::{ChainMap, Precedence};
+    ///
+    /// let chain: ChainMap<&str, i32> = ChainMap::new();
+    /// assert_eq!(chain.precedence(), Precedence::FirstWins);
+    /// ```
+    pub fn precedence(&self) -> Precedence {
+        self.precedence
+    }
+
+    /// Creates a [`ChainMapBuilder`] for assembling a chain one map at a
+    /// time.
     ///
     /// # Examples
     ///
@@ -117,55 +653,63 @@ impl<K, V, S> ChainMap<K, V, S> {
     /// let mut hash = HashMap::new();
     /// hash.insert("key", "value");
     ///
-    /// let mut chain = ChainMap::new();
-    /// chain.push_map(hash);
+    /// let chain = ChainMap::builder().layer(hash).build();
+    /// assert_eq!(chain["key"], "value");
     /// ```
     ///
-    /// [`usize`]: https://doc.rust-lang.org/std/primitive.usize.html
-    pub fn push_map(&mut self, map: HashMap<K, V, S>) {
-        self.inner.push(map)
+    /// [`ChainMapBuilder`]: struct.ChainMapBuilder.html
+    pub fn builder() -> ChainMapBuilder<K, V, S> {
+        ChainMapBuilder::new()
     }
-}
 
-impl<K, V, S> ChainMap<K, V, S>
-where
-    K: Hash + Eq,
-    S: BuildHasher,
-{
-    /// Returns `true` if the `ChainMap` contains a value for the given key.
+    /// Wraps the chain in a [`ResolvingChainMap`], which combines the values
+    /// for a key from every map using the given resolver.
     ///
-    /// As with [`HashMap::contains_key`], the supplied key may be any borrowed
-    /// form of the key type, but `Hash` and `Eq` on the borrowed form _must_
-    /// match those for the key type.
+    /// The resolver is called with the key and an iterator over all of its
+    /// values, from the highest-precedence map to the lowest, and may either
+    /// borrow one of those values or build a new one. The key is given in
+    /// the borrowed form `Q` that the resolving map is queried with, which
+    /// can be any type that `K` implements `Borrow` for.
     ///
     /// # Examples
     ///
     /// ```
+    /// use std::borrow::Cow;
     /// use std::collections::HashMap;
     /// use chain_map::ChainMap;
     ///
-    /// let mut hash = HashMap::new();
-    /// hash.insert("key", "value");
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("count", 1);
     ///
-    /// let mut chain = ChainMap::new();
-    /// chain.push_map(hash);
-    /// assert!(chain.contains_key("key"));
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("count", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let resolved = chain.with_resolver(|_, values| Cow::Owned(values.sum()));
+    ///
+    /// assert_eq!(resolved.get("count"), Some(Cow::Owned(3)));
     /// ```
     ///
-    /// [`HashMap::contains_key`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.contains_key
-    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    /// [`ResolvingChainMap`]: struct.ResolvingChainMap.html
+    pub fn with_resolver<Q, F>(self, resolver: F) -> ResolvingChainMap<K, V, S, F, Q>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
+        F: for<'a> Fn(&'a Q, GetAll<'a, K, V, S, Q>) -> Cow<'a, V>,
+        V: Clone,
     {
-        self.inner.iter().any(|map| map.contains_key(k))
+        ResolvingChainMap::new(self, resolver)
     }
 
-    /// Returns the highest-precedence value associated with the given key.
+    /// Adds a map to the chain.
     ///
-    /// As with [`HashMap::get`], the supplied key may be any borrowed form of
-    /// the key type, but `Hash` and `Eq` on the borrowed form _must_ match
-    /// those for the key type.
+    /// With the default [`Precedence::FirstWins`], the map is appended to the
+    /// lowest-precedence end of the chain. With [`Precedence::LastWins`], it
+    /// is added to the highest-precedence end instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of maps in the chain overflows a [`usize`].
     ///
     /// # Examples
     ///
@@ -178,138 +722,6068 @@ where
     ///
     /// let mut chain = ChainMap::new();
     /// chain.push_map(hash);
-    /// assert_eq!(chain.get("key"), Some(&"value"));
     /// ```
     ///
-    /// [`HashMap::get`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.get
-    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    /// [`Precedence::FirstWins`]: enum.Precedence.html#variant.FirstWins
+    /// [`Precedence::LastWins`]: enum.Precedence.html#variant.LastWins
+    /// [`usize`]: https://doc.rust-lang.org/std/primitive.usize.html
+    pub fn push_map(&mut self, map: HashMap<K, V, S>) {
+        self.push_link(map.into())
+    }
+
+    /// Adds a map with the given name to the chain.
+    ///
+    /// The map is placed according to the chain's [`precedence`], as with
+    /// [`push_map`]. The name can be used to find the map again with [`layer_by_name`] and
+    /// [`layer_index_by_name`]. Names don't need to be unique, lookups by
+    /// name will find the highest-precedence map with that name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("verbose", true);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_named_map("cli", hash);
+    ///
+    /// assert_eq!(chain.layer_index_by_name("cli"), Some(0));
+    /// ```
+    ///
+    /// [`precedence`]: #method.precedence
+    /// [`push_map`]: #method.push_map
+    /// [`layer_by_name`]: #method.layer_by_name
+    /// [`layer_index_by_name`]: #method.layer_index_by_name
+    pub fn push_named_map<N>(&mut self, name: N, map: HashMap<K, V, S>)
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
+        N: Into<String>,
     {
-        self.inner.iter().find_map(|map| map.get(k))
+        self.push_link(Link {
+            map,
+            name: Some(name.into()),
+            priority: 0,
+            tombstones: None,
+            enabled: true,
+            bloom: None,
+        })
     }
-}
 
-impl<K, V, S> Default for ChainMap<K, V, S> {
-    fn default() -> Self {
-        ChainMap { inner: Vec::new() }
-    }
-}
+    /// Adds a map to the chain with the given priority.
+    ///
+    /// Maps with a higher priority take precedence over maps with a lower
+    /// priority, regardless of the order in which they were added. The map is
+    /// placed after all existing maps with a higher priority and before all
+    /// maps with a lower priority. Among maps with equal priority, the
+    /// chain's [`precedence`] decides which takes precedence.
+    ///
+    /// Maps added by other methods have a priority of 0, but are placed
+    /// according to those methods rather than by priority.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut plugin = HashMap::new();
+    /// plugin.insert("timeout", 30);
+    ///
+    /// let mut builtin = HashMap::new();
+    /// builtin.insert("timeout", 10);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map_with_priority(plugin, 50);
+    /// chain.push_map_with_priority(builtin, 100);
+    ///
+    /// assert_eq!(chain["timeout"], 10);
+    /// assert_eq!(chain.layer_priority(1), Some(50));
+    /// ```
+    ///
+    /// [`precedence`]: #method.precedence
+    pub fn push_map_with_priority(&mut self, map: HashMap<K, V, S>, priority: i32) {
+        let precedence = self.precedence;
+        let index = self
+            .inner
+            .iter()
+            .position(|link| match precedence {
+                Precedence::FirstWins => link.priority < priority,
+                Precedence::LastWins => link.priority <= priority,
+            })
+            .unwrap_or(self.inner.len());
 
-impl<K, Q, V, S> Index<&Q> for ChainMap<K, V, S>
-where
-    K: Eq + Hash + Borrow<Q>,
-    Q: Eq + Hash + ?Sized,
-    S: BuildHasher,
-{
-    type Output = V;
+        self.inner.insert(
+            index,
+            Link {
+                map,
+                name: None,
+                priority,
+                tombstones: None,
+                enabled: true,
+                bloom: None,
+            },
+        );
+    }
 
-    fn index(&self, k: &Q) -> &V {
-        self.get(k).expect("no entry found for key")
+    fn push_link(&mut self, link: Link<K, V, S>) {
+        match self.precedence {
+            Precedence::FirstWins => self.inner.push(link),
+            Precedence::LastWins => self.inner.insert(0, link),
+        }
     }
-}
 
-impl<K, V, S> FromIterator<HashMap<K, V, S>> for ChainMap<K, V, S> {
-    fn from_iter<I>(iter: I) -> Self
+    // The index of the map that writes go to: the highest-precedence enabled
+    // map, or a new map at the front of the chain if none is enabled.
+    fn writable_index(&mut self) -> usize
     where
-        I: IntoIterator<Item = HashMap<K, V, S>>,
+        S: Default,
     {
-        ChainMap {
-            inner: Vec::from_iter(iter),
+        match self.inner.iter().position(|link| link.enabled) {
+            Some(index) => index,
+            None => {
+                self.inner.insert(0, HashMap::default().into());
+                0
+            }
         }
     }
-}
 
-impl<K, V, S> Extend<HashMap<K, V, S>> for ChainMap<K, V, S> {
-    fn extend<I>(&mut self, iter: I)
+    fn writable_link(&mut self) -> &mut Link<K, V, S>
     where
-        I: IntoIterator<Item = HashMap<K, V, S>>,
+        S: Default,
     {
-        self.inner.extend(iter)
+        let index = self.writable_index();
+        &mut self.inner[index]
     }
-}
 
-impl<K, V, S> Debug for ChainMap<K, V, S>
-where
-    K: Eq + Hash + Debug,
-    V: Debug,
-    S: BuildHasher,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ChainMap")
-            .field("inner", &self.inner)
-            .finish()
+    /// Prepends a map to the highest-precedence end of the chain.
+    ///
+    /// All existing maps are shifted down in precedence, so values in the new
+    /// map shadow any values already in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("key", "default");
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert("key", "override");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(defaults);
+    /// chain.push_front_map(overrides);
+    ///
+    /// assert_eq!(chain["key"], "override");
+    /// ```
+    pub fn push_front_map(&mut self, map: HashMap<K, V, S>) {
+        self.inner.insert(0, map.into())
+    }
+
+    /// Inserts a map into the chain at the given index, shifting all maps
+    /// after it down in precedence.
+    ///
+    /// Index 0 is the highest-precedence position, while an index equal to
+    /// the number of maps in the chain is the lowest-precedence position,
+    /// regardless of the chain's [`Precedence`]. Under
+    /// [`Precedence::FirstWins`] that is where [`push_map`] adds a map, but
+    /// under [`Precedence::LastWins`] [`push_map`] adds it at index 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of maps in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut user = HashMap::new();
+    /// user.insert("theme", "dark");
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("theme", "light");
+    /// defaults.insert("font", "mono");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![user, defaults].into_iter().collect();
+    ///
+    /// let mut project = HashMap::new();
+    /// project.insert("theme", "solarized");
+    /// project.insert("font", "sans");
+    /// chain.insert_map_at(1, project);
+    ///
+    /// assert_eq!(chain["theme"], "dark");
+    /// assert_eq!(chain["font"], "sans");
+    /// ```
+    ///
+    /// [`Precedence`]: enum.Precedence.html
+    /// [`Precedence::FirstWins`]: enum.Precedence.html#variant.FirstWins
+    /// [`Precedence::LastWins`]: enum.Precedence.html#variant.LastWins
+    /// [`push_map`]: #method.push_map
+    pub fn insert_map_at(&mut self, index: usize, map: HashMap<K, V, S>) {
+        self.inner.insert(index, map.into())
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash.clone());
+    ///
+    /// assert_eq!(chain.pop_map(), Some(hash));
+    /// assert_eq!(chain.pop_map(), None);
+    /// ```
+    pub fn pop_map(&mut self) -> Option<HashMap<K, V, S>> {
+        self.inner.pop().map(|link| link.map)
+    }
+
+    /// Removes the map at the given index from the chain and returns it, or
+    /// `None` if the index is out of bounds.
+    ///
+    /// All maps after the removed one are shifted up in precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "first");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "second");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let removed = chain.remove_map(0).unwrap();
+    /// assert_eq!(removed.get("key"), Some(&"first"));
+    /// assert_eq!(chain.get("key"), Some(&"second"));
+    /// assert!(chain.remove_map(1).is_none());
+    /// ```
+    pub fn remove_map(&mut self, index: usize) -> Option<HashMap<K, V, S>> {
+        if index < self.inner.len() {
+            Some(self.inner.remove(index).map)
+        } else {
+            None
+        }
+    }
+
+    /// Shortens the chain, keeping the first `len` maps and dropping the
+    /// lower-precedence rest.
+    ///
+    /// If `len` is greater than the number of maps in the chain, this has no
+    /// effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("second", 2);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// chain.truncate(1);
+    ///
+    /// assert!(chain.contains_key("first"));
+    /// assert!(!chain.contains_key("second"));
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        self.inner.truncate(len)
+    }
+
+    /// Splits the chain into two at the given index.
+    ///
+    /// Returns a newly allocated `ChainMap` containing the maps in the range
+    /// `[at, len)`, in the same precedence order. After the call, the
+    /// original chain will be left containing the maps `[0, at)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the number of maps in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("second", 2);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let fallback = chain.split_off(1);
+    ///
+    /// assert!(!chain.contains_key("second"));
+    /// assert!(fallback.contains_key("second"));
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> Self {
+        ChainMap {
+            inner: self.inner.drain(at..).collect(),
+            precedence: self.precedence,
+        }
+    }
+
+    /// Moves all the maps of `other` to the lowest-precedence end of this
+    /// chain.
+    ///
+    /// The maps keep their relative precedence, along with their names,
+    /// priorities, tombstones and enabled state, regardless of the
+    /// precedence of either chain. This is the inverse of [`split_off`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut request = HashMap::new();
+    /// request.insert("user", "alice");
+    ///
+    /// let mut process = HashMap::new();
+    /// process.insert("user", "nobody");
+    /// process.insert("host", "example.com");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![request].into_iter().collect();
+    /// let global: ChainMap<_, _> = vec![process].into_iter().collect();
+    /// chain.append(global);
+    ///
+    /// assert_eq!(chain["user"], "alice");
+    /// assert_eq!(chain["host"], "example.com");
+    /// assert_eq!(chain.source_of("host"), Some(1));
+    /// ```
+    ///
+    /// [`split_off`]: #method.split_off
+    pub fn append(&mut self, other: Self) {
+        self.inner.extend(other.inner);
+    }
+
+    /// Swaps the maps at the given indexes in the chain, exchanging their
+    /// precedence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` are out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "first");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "second");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// chain.swap_maps(0, 1);
+    ///
+    /// assert_eq!(chain["key"], "second");
+    /// ```
+    pub fn swap_maps(&mut self, a: usize, b: usize) {
+        self.inner.swap(a, b)
+    }
+
+    /// Moves the map at the given index to the highest-precedence end of the
+    /// chain.
+    ///
+    /// The relative order of all other maps in the chain is preserved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut work = HashMap::new();
+    /// work.insert("email", "me@work.example");
+    ///
+    /// let mut home = HashMap::new();
+    /// home.insert("email", "me@home.example");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![work, home].into_iter().collect();
+    /// chain.promote_map(1);
+    ///
+    /// assert_eq!(chain["email"], "me@home.example");
+    /// ```
+    pub fn promote_map(&mut self, index: usize) {
+        self.inner[..=index].rotate_right(1)
+    }
+
+    /// Rotates the chain in-place such that the first `mid` maps move to the
+    /// lowest-precedence end, while the remaining maps move up in precedence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the number of maps in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "first");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "second");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// chain.rotate_left(1);
+    ///
+    /// assert_eq!(chain["key"], "second");
+    /// ```
+    pub fn rotate_left(&mut self, mid: usize) {
+        self.inner.rotate_left(mid)
+    }
+
+    /// Rotates the chain in-place such that the last `k` maps move to the
+    /// highest-precedence end, while the remaining maps move down in
+    /// precedence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is greater than the number of maps in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "first");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "second");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// chain.rotate_right(1);
+    ///
+    /// assert_eq!(chain["key"], "second");
+    /// ```
+    pub fn rotate_right(&mut self, k: usize) {
+        self.inner.rotate_right(k)
+    }
+
+    /// Reverses the order of the maps in the chain, so the
+    /// lowest-precedence map becomes the highest-precedence one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "first");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "second");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// chain.reverse();
+    ///
+    /// assert_eq!(chain["key"], "second");
+    /// ```
+    pub fn reverse(&mut self) {
+        self.inner.reverse()
+    }
+
+    /// Returns a read-only view of the maps in the given range of the chain.
+    ///
+    /// Lookups through the view only consider the maps in the range, with
+    /// the same precedence they have in the chain, so a range that leaves
+    /// out the lowest-precedence maps shows what the values would be without
+    /// them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the number of maps in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut user = HashMap::new();
+    /// user.insert("theme", "dark");
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("theme", "light");
+    /// defaults.insert("font", "mono");
+    ///
+    /// let chain: ChainMap<_, _> = vec![user, defaults].into_iter().collect();
+    /// let user_provided = chain.view(..1);
+    ///
+    /// assert_eq!(user_provided.get("theme"), Some(&"dark"));
+    /// assert!(!user_provided.contains_key("font"));
+    /// assert_eq!(chain.view(1..).source_of("theme"), Some(1));
+    /// ```
+    pub fn view<R>(&self, range: R) -> ChainView<'_, K, V, S>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.inner.len(),
+        };
+        ChainView::new(&self.inner[start..end], start)
+    }
+
+    /// Returns a read-only view of the maps of this chain followed by the
+    /// maps of `other`, without copying or consuming either of them.
+    ///
+    /// Lookups through the view behave as they would after [`append`]: every
+    /// map in this chain takes precedence over every map in `other`,
+    /// regardless of the precedence of either chain, and tombstones in this
+    /// chain hide keys in `other`. [`ChainView::source_of`] numbers the maps
+    /// of `other` after the maps of this chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut process: ChainMap<_, _> = ChainMap::new();
+    /// process.insert("user", "nobody");
+    /// process.insert("host", "example.com");
+    ///
+    /// let mut request: ChainMap<_, _> = ChainMap::new();
+    /// request.insert("user", "alice");
+    ///
+    /// let view = request.chained_with(&process);
+    /// assert_eq!(view.get("user"), Some(&"alice"));
+    /// assert_eq!(view.get("host"), Some(&"example.com"));
+    /// assert_eq!(view.source_of("host"), Some(1));
+    /// ```
+    ///
+    /// [`append`]: #method.append
+    /// [`ChainView::source_of`]: struct.ChainView.html#method.source_of
+    pub fn chained_with<'a>(&'a self, other: &'a Self) -> ChainView<'a, K, V, S> {
+        ChainView::chained(&self.inner, &other.inner)
+    }
+
+    /// Returns a read-only view of the entries whose keys match the
+    /// predicate.
+    ///
+    /// Keys that don't match are hidden from every method of the view, which
+    /// is useful for handing a part of the chain, such as the keys in one
+    /// namespace, to code that shouldn't see the rest. The predicate is
+    /// checked on each access, so the view doesn't copy anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut config = HashMap::new();
+    /// config.insert("db.host", "localhost");
+    /// config.insert("db.port", "5432");
+    /// config.insert("log.level", "info");
+    ///
+    /// let chain: ChainMap<_, _> = vec![config].into_iter().collect();
+    /// let db = chain.filter_view(|key: &&str| key.starts_with("db."));
+    ///
+    /// assert_eq!(db.get(&"db.port"), Some(&"5432"));
+    /// assert_eq!(db.get(&"log.level"), None);
+    /// assert_eq!(db.len(), 2);
+    /// ```
+    pub fn filter_view<F>(&self, predicate: F) -> FilterView<'_, K, V, S, F>
+    where
+        F: Fn(&K) -> bool,
+    {
+        self.view(..).filter_view(predicate)
+    }
+
+    /// Returns a read-only view of the chain that transforms each value with
+    /// the given function as it is read.
+    ///
+    /// The function is called with the highest-precedence value for a key
+    /// each time the value is read through the view, so one chain of raw
+    /// values can be exposed as typed values without converting its maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut env = HashMap::new();
+    /// env.insert("port", "9000".to_string());
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("port", "8080".to_string());
+    /// defaults.insert("workers", "4".to_string());
+    ///
+    /// let chain: ChainMap<_, _> = vec![env, defaults].into_iter().collect();
+    /// let typed = chain.map_view(|value: &String| value.parse::<u16>().unwrap());
+    ///
+    /// assert_eq!(typed.get("port"), Some(9000));
+    /// assert_eq!(typed.get("workers"), Some(4));
+    /// ```
+    pub fn map_view<'a, F, T>(&'a self, f: F) -> MapView<'a, K, V, S, F>
+    where
+        F: Fn(&'a V) -> T,
+    {
+        self.view(..).map_view(f)
+    }
+
+    /// An iterator visiting the maps in the chain, in precedence order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut layers = chain.layers();
+    /// assert_eq!(layers.next().unwrap().get("first"), Some(&1));
+    /// assert_eq!(layers.next().unwrap().get("second"), Some(&2));
+    /// assert!(layers.next().is_none());
+    /// ```
+    pub fn layers(&self) -> Layers<'_, K, V, S> {
+        Layers {
+            inner: self.inner.iter(),
+        }
+    }
+
+    /// An iterator visiting the maps in the chain, in precedence order, with
+    /// mutable references to each map.
+    ///
+    /// Any Bloom filters built by [`enable_bloom_filters`] are discarded,
+    /// since keys may be added to the maps directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![HashMap::new(), HashMap::new()].into_iter().collect();
+    ///
+    /// for map in chain.layers_mut() {
+    ///     map.insert("key", "value");
+    /// }
+    ///
+    /// assert!(chain.layers().all(|map| map.get("key") == Some(&"value")));
+    /// ```
+    ///
+    /// [`enable_bloom_filters`]: #method.enable_bloom_filters
+    pub fn layers_mut(&mut self) -> LayersMut<'_, K, V, S> {
+        for link in &mut self.inner {
+            link.bloom = None;
+        }
+        LayersMut {
+            inner: self.inner.iter_mut(),
+        }
+    }
+
+    /// Returns a reference to the map at the given index in the chain, or
+    /// `None` if the index is out of bounds.
+    ///
+    /// Index 0 is the highest-precedence map. Indexing the chain with a
+    /// `usize` does the same, but panics if the index is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "override");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "default");
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.get_map(1).and_then(|map| map.get("key")), Some(&"default"));
+    /// assert_eq!(chain[0]["key"], "override");
+    /// assert!(chain.get_map(2).is_none());
+    /// ```
+    pub fn get_map(&self, index: usize) -> Option<&HashMap<K, V, S>> {
+        self.inner.get(index).map(|link| &link.map)
+    }
+
+    /// Returns a mutable reference to the map at the given index in the
+    /// chain, or `None` if the index is out of bounds.
+    ///
+    /// Index 0 is the highest-precedence map. Any Bloom filter built for the
+    /// map by [`enable_bloom_filters`] is discarded, since keys may be added
+    /// to the map directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", 1);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, HashMap::new()].into_iter().collect();
+    ///
+    /// if let Some(map) = chain.get_map_mut(0) {
+    ///     map.insert("key", 10);
+    /// }
+    ///
+    /// assert_eq!(chain.get("key"), Some(&10));
+    /// assert!(chain.get_map_mut(2).is_none());
+    /// ```
+    ///
+    /// [`enable_bloom_filters`]: #method.enable_bloom_filters
+    pub fn get_map_mut(&mut self, index: usize) -> Option<&mut HashMap<K, V, S>> {
+        self.inner.get_mut(index).map(|link| {
+            link.bloom = None;
+            &mut link.map
+        })
+    }
+
+    /// Returns the name of the map at the given index in the chain, or `None`
+    /// if the index is out of bounds or the map was added without a name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, i32> = ChainMap::new();
+    /// chain.push_named_map("cli", HashMap::new());
+    /// chain.push_map(HashMap::new());
+    ///
+    /// assert_eq!(chain.layer_name(0), Some("cli"));
+    /// assert_eq!(chain.layer_name(1), None);
+    /// ```
+    pub fn layer_name(&self, index: usize) -> Option<&str> {
+        self.inner.get(index).and_then(Link::name)
+    }
+
+    /// Returns the priority of the map at the given index in the chain, or
+    /// `None` if the index is out of bounds.
+    ///
+    /// Maps that were not added with [`push_map_with_priority`] have a
+    /// priority of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, i32> = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    /// chain.push_map_with_priority(HashMap::new(), 10);
+    ///
+    /// assert_eq!(chain.layer_priority(0), Some(10));
+    /// assert_eq!(chain.layer_priority(1), Some(0));
+    /// assert_eq!(chain.layer_priority(2), None);
+    /// ```
+    ///
+    /// [`push_map_with_priority`]: #method.push_map_with_priority
+    pub fn layer_priority(&self, index: usize) -> Option<i32> {
+        self.inner.get(index).map(|link| link.priority)
+    }
+
+    /// Returns whether the map at the given index in the chain is enabled,
+    /// or `None` if the index is out of bounds.
+    ///
+    /// See [`set_layer_enabled`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, i32> = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    ///
+    /// assert_eq!(chain.layer_enabled(0), Some(true));
+    /// assert_eq!(chain.layer_enabled(1), None);
+    /// ```
+    ///
+    /// [`set_layer_enabled`]: #method.set_layer_enabled
+    pub fn layer_enabled(&self, index: usize) -> Option<bool> {
+        self.inner.get(index).map(|link| link.enabled)
+    }
+
+    /// Enables or disables the map at the given index in the chain.
+    ///
+    /// A disabled map stays in the chain, but is skipped by lookups and
+    /// iteration over the unified view, along with any tombstones it holds.
+    /// Methods that work with a specific map, such as [`get_from_layer`] and
+    /// [`layers`], still see its contents. Writes through [`insert`] and
+    /// [`entry`] go to the highest-precedence map that is enabled, so a
+    /// disabled map is never written to by them. Maps are enabled when they
+    /// are added to the chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut experiment = HashMap::new();
+    /// experiment.insert("layout", "grid");
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("layout", "list");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![experiment, defaults].into_iter().collect();
+    ///
+    /// chain.set_layer_enabled(0, false);
+    /// assert_eq!(chain["layout"], "list");
+    ///
+    /// chain.set_layer_enabled(0, true);
+    /// assert_eq!(chain["layout"], "grid");
+    /// ```
+    ///
+    /// [`get_from_layer`]: #method.get_from_layer
+    /// [`layers`]: #method.layers
+    /// [`insert`]: #method.insert
+    /// [`entry`]: #method.entry
+    pub fn set_layer_enabled(&mut self, index: usize, enabled: bool) {
+        self.inner[index].enabled = enabled;
+    }
+
+    /// Returns the index of the highest-precedence map with the given name,
+    /// or `None` if no map in the chain has that name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, i32> = ChainMap::new();
+    /// chain.push_named_map("cli", HashMap::new());
+    /// chain.push_named_map("env", HashMap::new());
+    ///
+    /// assert_eq!(chain.layer_index_by_name("env"), Some(1));
+    /// assert_eq!(chain.layer_index_by_name("file"), None);
+    /// ```
+    pub fn layer_index_by_name(&self, name: &str) -> Option<usize> {
+        self.inner.iter().position(|link| link.name() == Some(name))
+    }
+
+    /// Returns a reference to the highest-precedence map with the given
+    /// name, or `None` if no map in the chain has that name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut env = HashMap::new();
+    /// env.insert("port", 8080);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_named_map("env", env);
+    ///
+    /// assert_eq!(chain.layer_by_name("env").unwrap()["port"], 8080);
+    /// assert!(chain.layer_by_name("cli").is_none());
+    /// ```
+    pub fn layer_by_name(&self, name: &str) -> Option<&HashMap<K, V, S>> {
+        self.layer_index_by_name(name)
+            .map(|index| &self.inner[index].map)
+    }
+}
+
+impl<K, V, S> ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Creates a `ChainMap` holding a single empty map that uses the given
+    /// hasher.
+    ///
+    /// The chain doesn't store a hasher of its own, so maps created by
+    /// [`push_empty_map`] and [`push_front_empty_map`] use a copy of the
+    /// hasher of the highest-precedence map in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain = ChainMap::with_hasher(RandomState::new());
+    /// chain.insert("key", "value");
+    ///
+    /// assert_eq!(chain["key"], "value");
+    /// assert_eq!(chain.layer_sizes(), vec![1]);
+    /// ```
+    ///
+    /// [`push_empty_map`]: #method.push_empty_map
+    /// [`push_front_empty_map`]: #method.push_front_empty_map
+    pub fn with_hasher(hash_builder: S) -> Self {
+        let mut chain = ChainMap::new();
+        chain.push_map(HashMap::with_hasher(hash_builder));
+        chain
+    }
+
+    /// Adds an empty map to the chain, as with [`push_map`].
+    ///
+    /// The new map uses a copy of the hasher of the highest-precedence map in
+    /// the chain, or the default hasher if the chain is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("theme", "light");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(defaults);
+    /// chain.push_empty_map();
+    ///
+    /// assert_eq!(chain.layer_sizes(), vec![1, 0]);
+    /// ```
+    ///
+    /// [`push_map`]: #method.push_map
+    pub fn push_empty_map(&mut self)
+    where
+        S: Clone + Default,
+    {
+        let map = self.empty_map();
+        self.push_map(map)
+    }
+
+    /// Adds an empty map to the highest-precedence end of the chain, as with
+    /// [`push_front_map`].
+    ///
+    /// The new map uses a copy of the hasher of the highest-precedence map in
+    /// the chain, or the default hasher if the chain is empty. This is useful
+    /// for adding a layer of overrides on top of the existing maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("theme", "light");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(defaults);
+    /// chain.push_front_empty_map();
+    /// chain.insert("theme", "dark");
+    ///
+    /// assert_eq!(chain["theme"], "dark");
+    /// assert_eq!(chain.layer_sizes(), vec![1, 1]);
+    /// ```
+    ///
+    /// [`push_front_map`]: #method.push_front_map
+    pub fn push_front_empty_map(&mut self)
+    where
+        S: Clone + Default,
+    {
+        let map = self.empty_map();
+        self.push_front_map(map)
+    }
+
+    /// Creates an empty map with a copy of the hasher of the
+    /// highest-precedence map.
+    fn empty_map(&self) -> HashMap<K, V, S>
+    where
+        S: Clone + Default,
+    {
+        let hash_builder = self
+            .inner
+            .first()
+            .map_or_else(S::default, |link| link.map.hasher().clone());
+        HashMap::with_hasher(hash_builder)
+    }
+
+    /// Returns `true` if the `ChainMap` contains a value for the given key.
+    ///
+    /// As with [`HashMap::contains_key`], the supplied key may be any borrowed
+    /// form of the key type, but `Hash` and `Eq` on the borrowed form _must_
+    /// match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    /// assert!(chain.contains_key("key"));
+    /// ```
+    ///
+    /// [`HashMap::contains_key`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.contains_key
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .iter()
+            .any(|link| link.may_contain(&probe) && link.provides(k))
+    }
+
+    /// Returns `true` if the map at the given index in the chain contains a
+    /// value for the given key.
+    ///
+    /// Returns `false` if the index is out of bounds. To find which map
+    /// provides the value for a key, use [`source_of`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert!(chain.contains_key_in_layer(1, "first"));
+    /// assert!(!chain.contains_key_in_layer(0, "second"));
+    /// assert!(!chain.contains_key_in_layer(2, "first"));
+    /// ```
+    ///
+    /// [`source_of`]: #method.source_of
+    pub fn contains_key_in_layer<Q>(&self, index: usize, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner
+            .get(index)
+            .map_or(false, |link| link.map.contains_key(k))
+    }
+
+    /// Returns the highest-precedence value associated with the given key.
+    ///
+    /// As with [`HashMap::get`], the supplied key may be any borrowed form of
+    /// the key type, but `Hash` and `Eq` on the borrowed form _must_ match
+    /// those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    /// assert_eq!(chain.get("key"), Some(&"value"));
+    /// ```
+    ///
+    /// [`HashMap::get`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.get
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .iter()
+            .filter(|link| link.may_contain(&probe))
+            .find_map(|link| link.lookup(k))
+    }
+
+    /// Returns the highest-precedence value associated with the given key, or
+    /// a [`KeyError`] recording the key if it is not in the chain.
+    ///
+    /// This behaves like [`get`], but the missing key is kept in the error so
+    /// it can be reported. Call [`KeyError::suggest`] on the error to find
+    /// similarly spelled keys that are in the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`KeyError`] if no map in the chain provides a value for the
+    /// key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("port".to_string(), 8080);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    ///
+    /// assert_eq!(chain.try_get("port"), Ok(&8080));
+    ///
+    /// let error = chain.try_get("host").unwrap_err();
+    /// assert_eq!(error.key(), "host");
+    /// assert_eq!(error.to_string(), "no entry found for key \"host\"");
+    /// ```
+    ///
+    /// [`KeyError`]: struct.KeyError.html
+    /// [`get`]: #method.get
+    /// [`KeyError::suggest`]: struct.KeyError.html#method.suggest
+    pub fn try_get<Q>(&self, k: &Q) -> Result<&V, KeyError<K>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.get(k).ok_or_else(|| KeyError::new(k.to_owned()))
+    }
+
+    /// Returns a clone of the highest-precedence value associated with the
+    /// given key, or the default value of `V` if the key is not in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("retries", 5);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    ///
+    /// assert_eq!(chain.get_or_default("retries"), 5);
+    /// assert_eq!(chain.get_or_default("timeout"), 0);
+    /// ```
+    pub fn get_or_default<Q>(&self, k: &Q) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone + Default,
+    {
+        self.get(k).cloned().unwrap_or_default()
+    }
+
+    /// Returns a clone of the highest-precedence value associated with the
+    /// given key, or the result of calling `f` if the key is not in the
+    /// chain.
+    ///
+    /// `f` is only called when the key is missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("host", "db.internal".to_string());
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    ///
+    /// assert_eq!(chain.get_or_else("host", || "localhost".to_string()), "db.internal");
+    /// assert_eq!(chain.get_or_else("user", || "admin".to_string()), "admin");
+    /// ```
+    pub fn get_or_else<Q, F>(&self, k: &Q, f: F) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+        F: FnOnce() -> V,
+    {
+        self.get(k).cloned().unwrap_or_else(f)
+    }
+
+    /// Returns the highest-precedence value associated with each of the given
+    /// keys, in the same order as the keys.
+    ///
+    /// The result is the same as calling [`get`] for each key, but the chain
+    /// is walked once for the whole batch, looking up every unresolved key in
+    /// each map before moving on to the next one. This is faster than
+    /// separate lookups when resolving many keys in a long chain. With the
+    /// `const-generics` feature, [`get_many`] does the same for an array of
+    /// keys, returning an array of values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(
+    ///     chain.get_many_slice(&["first", "second", "third"]),
+    ///     vec![Some(&1), Some(&2), None]
+    /// );
+    /// ```
+    ///
+    /// [`get`]: #method.get
+    /// [`get_many`]: #method.get_many
+    pub fn get_many_slice<Q>(&self, keys: &[&Q]) -> Vec<Option<&V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut values = vec![None; keys.len()];
+        self.resolve_many(keys, &mut values);
+        values
+    }
+
+    /// Looks up each of the keys in a single walk over the chain, storing
+    /// the highest-precedence value for each key in the matching slot of
+    /// `values`, which must start out empty.
+    fn resolve_many<'a, Q>(&'a self, keys: &[&Q], values: &mut [Option<&'a V>])
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probes: Vec<_> = keys.iter().map(|&key| Probe::new(key)).collect();
+        // A key is resolved once a value is found or a tombstone hides it from
+        // the rest of the chain.
+        let mut resolved = vec![false; keys.len()];
+        let mut remaining = keys.len();
+        for link in self.inner.iter().filter(|link| link.enabled) {
+            if remaining == 0 {
+                break;
+            }
+            for (index, &key) in keys.iter().enumerate() {
+                if resolved[index] {
+                    continue;
+                }
+                if link.may_contain(&probes[index]) {
+                    values[index] = link.map.get(key);
+                }
+                if values[index].is_some() || link.masks(key) {
+                    resolved[index] = true;
+                    remaining -= 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the map that provides the highest-precedence
+    /// value for the given key, or `None` if the key is not in the chain.
+    ///
+    /// As with [`HashMap::get`], the supplied key may be any borrowed form of
+    /// the key type, but `Hash` and `Eq` on the borrowed form _must_ match
+    /// those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.source_of("first"), Some(0));
+    /// assert_eq!(chain.source_of("second"), Some(1));
+    /// assert_eq!(chain.source_of("third"), None);
+    /// ```
+    ///
+    /// [`HashMap::get`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.get
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .iter()
+            .position(|link| link.may_contain(&probe) && link.provides(k))
+    }
+
+    /// Returns the name of the map that provides the highest-precedence value
+    /// for the given key.
+    ///
+    /// Returns `None` if the key is not in the chain, or if the map that
+    /// contains it was added without a name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut cli = HashMap::new();
+    /// cli.insert("verbose", true);
+    ///
+    /// let mut env = HashMap::new();
+    /// env.insert("verbose", false);
+    /// env.insert("color", true);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_named_map("cli", cli);
+    /// chain.push_named_map("env", env);
+    ///
+    /// assert_eq!(chain.source_name_of("verbose"), Some("cli"));
+    /// assert_eq!(chain.source_name_of("color"), Some("env"));
+    /// ```
+    pub fn source_name_of<Q>(&self, k: &Q) -> Option<&str>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.source_of(k).and_then(|index| self.inner[index].name())
+    }
+
+    /// Returns the highest-precedence value associated with the given key,
+    /// along with the [`LayerId`] of the map that provides it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut cli = HashMap::new();
+    /// cli.insert("verbose", true);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    /// chain.push_named_map("cli", cli);
+    ///
+    /// let (value, source) = chain.get_with_source("verbose").unwrap();
+    /// assert!(*value);
+    /// assert_eq!(source.index(), 1);
+    /// assert_eq!(source.name(), Some("cli"));
+    /// assert_eq!(source.to_string(), "cli");
+    /// ```
+    ///
+    /// [`LayerId`]: struct.LayerId.html
+    pub fn get_with_source<Q>(&self, k: &Q) -> Option<(&V, LayerId<'_>)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        let links = self.visible_links(k);
+        links.iter().enumerate().find_map(|(index, link)| {
+            if !link.may_contain(&probe) {
+                return None;
+            }
+            link.lookup(k).map(|value| {
+                let source = LayerId {
+                    index,
+                    name: link.name(),
+                };
+                (value, source)
+            })
+        })
+    }
+
+    /// An iterator visiting every value associated with the given key, from
+    /// the highest-precedence map to the lowest.
+    ///
+    /// As with [`HashMap::get`], the supplied key may be any borrowed form of
+    /// the key type, but `Hash` and `Eq` on the borrowed form _must_ match
+    /// those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut user = HashMap::new();
+    /// user.insert("path", "/home/user/bin");
+    ///
+    /// let mut system = HashMap::new();
+    /// system.insert("path", "/usr/bin");
+    ///
+    /// let chain: ChainMap<_, _> = vec![user, HashMap::new(), system].into_iter().collect();
+    ///
+    /// let paths: Vec<_> = chain.get_all("path").collect();
+    /// assert_eq!(paths, vec![&"/home/user/bin", &"/usr/bin"]);
+    /// ```
+    ///
+    /// [`HashMap::get`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.get
+    pub fn get_all<'a, Q>(&'a self, k: &'a Q) -> GetAll<'a, K, V, S, Q>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        GetAll {
+            maps: self.visible_links(k).iter(),
+            key: k,
+        }
+    }
+
+    /// Folds every value associated with the given key, from the
+    /// lowest-precedence map to the highest.
+    ///
+    /// Starting from `init`, `f` is called with the accumulator and each
+    /// value in turn, so higher-precedence values are applied last. If no map
+    /// contains the key, `init` is returned unchanged.
+    ///
+    /// As with [`HashMap::get`], the supplied key may be any borrowed form of
+    /// the key type, but `Hash` and `Eq` on the borrowed form _must_ match
+    /// those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut user = HashMap::new();
+    /// user.insert("path", "/home/user/bin");
+    ///
+    /// let mut system = HashMap::new();
+    /// system.insert("path", "/usr/bin");
+    ///
+    /// let chain: ChainMap<_, _> = vec![user, system].into_iter().collect();
+    ///
+    /// let path = chain.get_merged("path", String::new(), |mut acc, value| {
+    ///     if !acc.is_empty() {
+    ///         acc.push(':');
+    ///     }
+    ///     acc.push_str(value);
+    ///     acc
+    /// });
+    /// assert_eq!(path, "/usr/bin:/home/user/bin");
+    /// ```
+    ///
+    /// [`HashMap::get`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.get
+    pub fn get_merged<Q, T, F>(&self, k: &Q, init: T, f: F) -> T
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnMut(T, &V) -> T,
+    {
+        self.visible_links(k)
+            .iter()
+            .rev()
+            .filter_map(|link| link.lookup(k))
+            .fold(init, f)
+    }
+
+    /// Returns a mutable reference to the highest-precedence value associated
+    /// with the given key.
+    ///
+    /// Only the value in the highest-precedence map containing the key is
+    /// accessible, values in lower-precedence maps are left untouched. As with
+    /// [`HashMap::get_mut`], the supplied key may be any borrowed form of the
+    /// key type, but `Hash` and `Eq` on the borrowed form _must_ match those
+    /// for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", vec!["value"]);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    ///
+    /// if let Some(values) = chain.get_mut("key") {
+    ///     values.push("other");
+    /// }
+    /// assert_eq!(chain["key"], vec!["value", "other"]);
+    /// ```
+    ///
+    /// [`HashMap::get_mut`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.get_mut
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.inner[index].map.get_mut(k)
+    }
+
+    /// Returns the stored key and a mutable reference to the
+    /// highest-precedence value associated with the given key.
+    ///
+    /// As with [`get_mut`], only the value in the highest-precedence map
+    /// containing the key is accessible. The key is the one stored in that
+    /// map, so it can be used to tell which of several equal keys was
+    /// matched.
+    ///
+    /// # Performance
+    ///
+    /// With the `std` feature, which is enabled by default, the standard
+    /// library's `HashMap` offers no way to look up the stored key on the
+    /// minimum supported Rust version, so this scans every entry of the map
+    /// that provides the value. It runs in O(M), with `M` the number of
+    /// entries in that map, rather than in the O(1) of [`get_mut`], so
+    /// prefer [`get_mut`] when the stored key isn't needed. Without `std`,
+    /// the `hashbrown` map is queried directly and the lookup takes the same
+    /// time as [`get_mut`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("retries".to_string(), 5);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("retries".to_string(), 3);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// if let Some((key, value)) = chain.get_key_value_mut("retries") {
+    ///     assert_eq!(key, "retries");
+    ///     *value += 1;
+    /// }
+    /// assert_eq!(chain["retries"], 6);
+    /// assert_eq!(chain.get_from_layer(1, "retries"), Some(&3));
+    /// ```
+    ///
+    /// [`get_mut`]: #method.get_mut
+    pub fn get_key_value_mut<Q>(&mut self, k: &Q) -> Option<(&K, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.inner[index].key_value_mut(k)
+    }
+
+    /// Returns the value associated with the given key in the map at the
+    /// given index, ignoring precedence.
+    ///
+    /// Returns `None` if the index is out of bounds or if that map does not
+    /// contain the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "override");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "default");
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.get("key"), Some(&"override"));
+    /// assert_eq!(chain.get_from_layer(1, "key"), Some(&"default"));
+    /// assert_eq!(chain.get_from_layer(2, "key"), None);
+    /// ```
+    pub fn get_from_layer<Q>(&self, index: usize, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get(index).and_then(|link| link.map.get(k))
+    }
+
+    /// Inserts a key-value pair into the highest-precedence map in the chain.
+    ///
+    /// Disabled maps are skipped, so the entry goes into the first enabled
+    /// map. If the chain has no enabled map, a new empty map is first added
+    /// at the highest-precedence end to hold the entry. Lower-precedence maps
+    /// are never modified, so any values they hold for the key become
+    /// shadowed by the new value.
+    ///
+    /// Returns the value previously associated with the key in the
+    /// highest-precedence map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    /// chain.push_map(hash);
+    ///
+    /// assert_eq!(chain.insert("key", "override"), None);
+    /// assert_eq!(chain["key"], "override");
+    /// ```
+    pub fn insert(&mut self, k: K, v: V) -> Option<V>
+    where
+        S: Default,
+    {
+        let top = self.writable_link();
+        top.note_insert(&k);
+        top.map.insert(k, v)
+    }
+
+    /// Removes a key from the highest-precedence map that contains it,
+    /// returning the value at the key if the key was present in the chain.
+    ///
+    /// Only a single occurrence of the key is removed, so a value for the
+    /// same key in a lower-precedence map will become visible.
+    ///
+    /// As with [`HashMap::remove`], the supplied key may be any borrowed form
+    /// of the key type, but `Hash` and `Eq` on the borrowed form _must_ match
+    /// those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "override");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "default");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.remove("key"), Some("override"));
+    /// assert_eq!(chain.get("key"), Some(&"default"));
+    /// ```
+    ///
+    /// [`HashMap::remove`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.remove
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        self.inner[index].map.remove(k)
+    }
+
+    /// Removes a key from every map in the chain, returning all of the
+    /// removed values in precedence order.
+    ///
+    /// The returned `Vec` is empty if no map contained the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "override");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "default");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.remove_all("key"), vec!["override", "default"]);
+    /// assert!(!chain.contains_key("key"));
+    /// ```
+    pub fn remove_all<Q>(&mut self, k: &Q) -> Vec<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner
+            .iter_mut()
+            .filter_map(|link| link.map.remove(k))
+            .collect()
+    }
+
+    /// Records a tombstone for the key in the highest-precedence enabled map,
+    /// so that the key is treated as deleted from the chain.
+    ///
+    /// Any entry for the key in that map is removed, and entries for the key
+    /// in lower-precedence maps are hidden from lookups and iteration, even
+    /// though they remain in their maps. As with [`insert`], if the chain has
+    /// no enabled map, a new map is added to hold the tombstone.
+    ///
+    /// A tombstone only hides entries in maps after the one that holds it,
+    /// and an entry for the key in the same map takes precedence over the
+    /// tombstone. Tombstones are kept until they are removed with
+    /// [`remove_tombstone`] or the map is cleared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut globals = HashMap::new();
+    /// globals.insert("x", 1);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(globals);
+    ///
+    /// let mut scope = chain.new_child();
+    /// scope.insert_tombstone("x");
+    ///
+    /// assert_eq!(scope.get("x"), None);
+    /// assert_eq!(scope.parents().get("x"), Some(&1));
+    /// ```
+    ///
+    /// [`insert`]: #method.insert
+    /// [`remove_tombstone`]: #method.remove_tombstone
+    pub fn insert_tombstone(&mut self, k: K)
+    where
+        S: Default,
+    {
+        let top = self.writable_link();
+        top.map.remove(&k);
+        top.tombstones.get_or_insert_with(HashSet::new).insert(k);
+    }
+
+    /// Removes the tombstone for the key from the highest-precedence enabled
+    /// map, returning `true` if there was one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut globals = HashMap::new();
+    /// globals.insert("x", 1);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    /// chain.push_map(globals);
+    ///
+    /// chain.insert_tombstone("x");
+    /// assert!(!chain.contains_key("x"));
+    ///
+    /// assert!(chain.remove_tombstone("x"));
+    /// assert_eq!(chain["x"], 1);
+    /// ```
+    pub fn remove_tombstone<Q>(&mut self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner
+            .iter_mut()
+            .find(|link| link.enabled)
+            .and_then(|link| link.tombstones.as_mut())
+            .map_or(false, |tombstones| tombstones.remove(k))
+    }
+
+    /// Retains only the entries of the unified view specified by the
+    /// predicate.
+    ///
+    /// The predicate is called once for each key, with its
+    /// highest-precedence value. If it returns `false`, the key is removed
+    /// from every map in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// chain.retain(|_, value| *value % 2 == 0);
+    /// assert_eq!(chain.get("first"), None);
+    /// assert_eq!(chain.get("second"), Some(&2));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for index in 0..self.inner.len() {
+            let (earlier, rest) = self.inner.split_at_mut(index);
+            let (current, later) = rest.split_first_mut().unwrap();
+            if !current.enabled {
+                continue;
+            }
+
+            current.map.retain(|key, value| {
+                if earlier.iter().any(|link| link.hides(key)) || f(key, value) {
+                    return true;
+                }
+                for link in later.iter_mut() {
+                    link.map.remove(key);
+                }
+                false
+            });
+        }
+    }
+
+    /// Clears every map in the chain, removing all entries.
+    ///
+    /// The maps themselves are kept in the chain, along with their allocated
+    /// memory, so the structure of the chain is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(hash);
+    /// chain.clear();
+    ///
+    /// assert!(chain.is_empty());
+    /// assert_eq!(chain.layer_sizes(), vec![0]);
+    /// ```
+    pub fn clear(&mut self) {
+        for link in &mut self.inner {
+            link.map.clear();
+            link.tombstones = None;
+        }
+    }
+
+    /// Clears the map at the given index in the chain, removing all of its
+    /// entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("key", "override");
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("key", "default");
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// chain.clear_layer(0);
+    ///
+    /// assert_eq!(chain.get("key"), Some(&"default"));
+    /// ```
+    pub fn clear_layer(&mut self, index: usize) {
+        let link = &mut self.inner[index];
+        link.map.clear();
+        link.tombstones = None;
+    }
+
+    /// Gets the entry for the given key in the chain, for in-place
+    /// manipulation.
+    ///
+    /// If the key is present in the chain, the entry refers to its
+    /// highest-precedence occurrence. Otherwise the entry is vacant and
+    /// inserting into it will add the key to the highest-precedence enabled
+    /// map, adding a new empty map first if the chain has no enabled map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("count", 1);
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    /// chain.push_map(hash);
+    ///
+    /// *chain.entry("count").or_insert(0) += 1;
+    /// *chain.entry("other").or_insert(0) += 1;
+    ///
+    /// assert_eq!(chain["count"], 2);
+    /// assert_eq!(chain["other"], 1);
+    /// assert_eq!(chain.layer_sizes(), vec![1, 1]);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S>
+    where
+        S: Default,
+    {
+        let index = match self.source_of(&key) {
+            Some(index) => index,
+            None => {
+                let index = self.writable_index();
+                self.inner[index].note_insert(&key);
+                index
+            }
+        };
+
+        match self.inner[index].map.entry(key) {
+            hash_map::Entry::Occupied(inner) => Entry::Occupied(OccupiedEntry {
+                inner,
+                marker: PhantomData,
+            }),
+            hash_map::Entry::Vacant(inner) => Entry::Vacant(VacantEntry {
+                inner,
+                marker: PhantomData,
+            }),
+        }
+    }
+
+    /// Returns a mutable reference to the highest-precedence value associated
+    /// with the given key, first inserting the result of calling `f` into the
+    /// highest-precedence enabled map if the key is not in the chain.
+    ///
+    /// This is a shorthand for `entry(key).or_insert_with(f)`. `f` is only
+    /// called when the key is missing from every map, so a value in a
+    /// lower-precedence map is returned rather than shadowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut shared = HashMap::new();
+    /// shared.insert(10, 55);
+    ///
+    /// let mut cache = ChainMap::new();
+    /// cache.push_map(HashMap::new());
+    /// cache.push_map(shared);
+    ///
+    /// assert_eq!(*cache.get_or_insert_with(10, || unreachable!()), 55);
+    /// assert_eq!(*cache.get_or_insert_with(12, || 144), 144);
+    /// assert_eq!(cache.source_of(&12), Some(0));
+    /// ```
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> &mut V
+    where
+        S: Default,
+        F: FnOnce() -> V,
+    {
+        self.entry(key).or_insert_with(f)
+    }
+
+    /// Adds a new, empty map to the highest-precedence end of the chain,
+    /// returning a guard that removes it again when dropped.
+    ///
+    /// The guard dereferences to the chain, so values inserted through it go
+    /// into the new map and shadow the rest of the chain until the guard goes
+    /// out of scope. Because the map is removed in the guard's `Drop`
+    /// implementation, it is removed even if the enclosing block panics.
+    /// Scopes can be nested by calling `push_scope` on the guard.
+    ///
+    /// When the guard is dropped it removes the highest-precedence map, so
+    /// adding maps to the front of the chain through the guard will cause a
+    /// different map to be removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<_, _> = ChainMap::new();
+    /// chain.insert("x", 1);
+    ///
+    /// {
+    ///     let mut scope = chain.push_scope();
+    ///     scope.insert("x", 2);
+    ///     assert_eq!(scope["x"], 2);
+    /// }
+    ///
+    /// assert_eq!(chain["x"], 1);
+    /// ```
+    pub fn push_scope(&mut self) -> Scope<'_, K, V, S>
+    where
+        S: Default,
+    {
+        self.inner.insert(0, HashMap::default().into());
+        Scope { chain: self }
+    }
+
+    /// Starts a transaction that stages changes to the chain without
+    /// affecting the chain until they are committed.
+    ///
+    /// Changes made through the returned [`Transaction`] are held in a
+    /// staging map that takes precedence over the rest of the chain, so
+    /// lookups through the transaction see the staged changes and can be used
+    /// to validate them. [`Transaction::commit`] merges the changes into the
+    /// highest-precedence map, while [`Transaction::rollback`], or dropping
+    /// the transaction, discards them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<_, _> = ChainMap::new();
+    /// chain.insert("width", 10);
+    ///
+    /// let mut transaction = chain.begin_transaction();
+    /// transaction.insert("width", 20);
+    /// transaction.insert("height", 5);
+    /// assert_eq!(transaction["width"], 20);
+    /// transaction.rollback();
+    ///
+    /// assert_eq!(chain["width"], 10);
+    /// assert!(!chain.contains_key("height"));
+    ///
+    /// let mut transaction = chain.begin_transaction();
+    /// transaction.insert("width", 20);
+    /// transaction.commit();
+    ///
+    /// assert_eq!(chain["width"], 20);
+    /// ```
+    ///
+    /// [`Transaction`]: struct.Transaction.html
+    /// [`Transaction::commit`]: struct.Transaction.html#method.commit
+    /// [`Transaction::rollback`]: struct.Transaction.html#method.rollback
+    pub fn begin_transaction(&mut self) -> Transaction<'_, K, V, S>
+    where
+        S: Default,
+    {
+        self.inner.insert(0, HashMap::default().into());
+        Transaction {
+            chain: self,
+            finished: false,
+        }
+    }
+
+    /// Consumes the chain, collapsing its unified view into a single
+    /// `HashMap`.
+    ///
+    /// The resulting map contains each key in the chain exactly once,
+    /// associated with its highest-precedence value. Shadowed entries are
+    /// dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let flat = chain.into_flattened();
+    ///
+    /// assert_eq!(flat.len(), 2);
+    /// assert_eq!(flat["first"], 1);
+    /// assert_eq!(flat["second"], 2);
+    /// ```
+    pub fn into_flattened(self) -> HashMap<K, V, S>
+    where
+        S: Default,
+    {
+        self.into_iter().collect()
+    }
+
+    /// Converts the chain into a read-only [`FrozenChainMap`], which looks up
+    /// keys in constant time rather than searching each map in turn.
+    ///
+    /// Building the index visits every entry in the chain once, so this is
+    /// worthwhile when the chain will be read many times without being
+    /// changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let frozen = chain.freeze();
+    ///
+    /// assert_eq!(frozen["first"], 1);
+    /// assert_eq!(frozen["second"], 2);
+    /// ```
+    ///
+    /// [`FrozenChainMap`]: struct.FrozenChainMap.html
+    pub fn freeze(self) -> FrozenChainMap<K, V, S>
+    where
+        K: Clone,
+        S: Default,
+    {
+        FrozenChainMap::new(self)
+    }
+
+    /// Wraps the chain in a [`CachingChainMap`], which remembers the map that
+    /// provided the value for each of the `capacity` most recently used keys.
+    ///
+    /// Repeated lookups of the same key then only check a single map, rather
+    /// than searching the chain. When the cache is full, the least recently
+    /// used key is evicted. The cache is cleared whenever the chain is
+    /// mutated through the wrapper, and its hit rate can be read with
+    /// [`CachingChainMap::cache_stats`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let cached = chain.with_lookup_cache(16);
+    ///
+    /// assert_eq!(cached.get(&"second"), Some(&2));
+    /// assert_eq!(cached.get(&"second"), Some(&2));
+    /// assert_eq!(cached.cache_capacity(), 16);
+    /// ```
+    ///
+    /// [`CachingChainMap`]: struct.CachingChainMap.html
+    /// [`CachingChainMap::cache_stats`]: struct.CachingChainMap.html#method.cache_stats
+    pub fn with_lookup_cache(self, capacity: usize) -> CachingChainMap<K, V, S> {
+        CachingChainMap::new(self, capacity)
+    }
+
+    /// Wraps the chain in a [`CachingChainMap`] that remembers up to
+    /// `capacity` keys found to be missing from every map in the chain.
+    ///
+    /// Repeated lookups of a missing key then return immediately, rather
+    /// than checking every map, while keys that are found are not cached.
+    /// When the cache is full, the least recently used key is evicted. The
+    /// cache is cleared whenever the chain is mutated through the wrapper.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    ///
+    /// let chain: ChainMap<_, _> = vec![hash].into_iter().collect();
+    /// let cached = chain.with_negative_cache(1024);
+    ///
+    /// assert_eq!(cached.get(&"missing"), None);
+    /// assert!(cached.is_known_absent(&"missing"));
+    /// ```
+    ///
+    /// [`CachingChainMap`]: struct.CachingChainMap.html
+    pub fn with_negative_cache(self, capacity: usize) -> CachingChainMap<K, V, S> {
+        CachingChainMap::negative(self, capacity)
+    }
+
+    /// Builds a Bloom filter over the keys of each map in the chain, so
+    /// lookups for keys that aren't in a map can usually skip it without
+    /// hashing into the map itself.
+    ///
+    /// The key is hashed once per lookup and checked against every filter,
+    /// which makes lookups of absent keys much cheaper in long chains. Keys
+    /// added through [`insert`] and [`entry`] are recorded in the filter, and
+    /// the filter is rebuilt as it fills up. Maps added to the chain after
+    /// this is called don't have filters until it is called again, and the
+    /// filter for a map is discarded when it is borrowed mutably through
+    /// [`get_map_mut`] or [`layers_mut`]. Maps without a filter are always
+    /// checked, so lookups return the same results either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut flags = HashMap::new();
+    /// flags.insert("dark-mode", true);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![HashMap::new(), flags].into_iter().collect();
+    /// chain.enable_bloom_filters();
+    /// chain.insert("beta", false);
+    ///
+    /// assert_eq!(chain.get("dark-mode"), Some(&true));
+    /// assert_eq!(chain.get("beta"), Some(&false));
+    /// assert_eq!(chain.get("missing"), None);
+    /// ```
+    ///
+    /// [`insert`]: #method.insert
+    /// [`entry`]: #method.entry
+    /// [`get_map_mut`]: #method.get_map_mut
+    /// [`layers_mut`]: #method.layers_mut
+    pub fn enable_bloom_filters(&mut self) {
+        for link in &mut self.inner {
+            link.build_bloom();
+        }
+    }
+
+    /// Discards the Bloom filters built by [`enable_bloom_filters`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![HashMap::new()].into_iter().collect();
+    /// chain.enable_bloom_filters();
+    /// chain.disable_bloom_filters();
+    /// chain.insert("key", 1);
+    ///
+    /// assert_eq!(chain.get("key"), Some(&1));
+    /// ```
+    ///
+    /// [`enable_bloom_filters`]: #method.enable_bloom_filters
+    pub fn disable_bloom_filters(&mut self) {
+        for link in &mut self.inner {
+            link.bloom = None;
+        }
+    }
+
+    /// Merges the maps in the given range into a single map, which takes the
+    /// place of the range in the chain.
+    ///
+    /// Precedence within the range is preserved, so the merged map holds the
+    /// highest-precedence value from the range for each key. Disabled maps in
+    /// the range are left in place with their entries, tombstones and name
+    /// untouched, so they can still be re-enabled later, and each run of
+    /// adjacent enabled maps between them is merged separately. Maps outside
+    /// of the range are not modified. An empty range leaves the chain
+    /// unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the number of maps in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let maps = (0..4).map(|index| {
+    ///     let mut map = HashMap::new();
+    ///     map.insert("shared", index);
+    ///     map.insert(["zero", "one", "two", "three"][index], index);
+    ///     map
+    /// });
+    ///
+    /// let mut chain: ChainMap<_, _> = maps.collect();
+    /// chain.squash(1..3);
+    ///
+    /// assert_eq!(chain.layer_sizes(), vec![2, 3, 2]);
+    /// assert_eq!(chain.layers().nth(1).unwrap()["shared"], 1);
+    /// assert_eq!(chain["shared"], 0);
+    /// ```
+    pub fn squash<R>(&mut self, range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+
+        // Each run of adjacent enabled maps is merged into its first map,
+        // while disabled maps keep their place between the runs.
+        let mut squashed: Vec<Link<K, V, S>> = Vec::new();
+        for link in self.inner.drain(range) {
+            match squashed.last_mut() {
+                Some(last) if last.enabled && link.enabled => {
+                    for (key, value) in link.map {
+                        if !last.masks(&key) {
+                            last.map.entry(key).or_insert(value);
+                        }
+                    }
+                    if let Some(tombstones) = link.tombstones {
+                        last.tombstones
+                            .get_or_insert_with(HashSet::new)
+                            .extend(tombstones);
+                    }
+                }
+                _ => squashed.push(link),
+            }
+        }
+
+        for (offset, mut link) in squashed.into_iter().enumerate() {
+            if link.enabled && link.bloom.is_some() {
+                link.build_bloom();
+            }
+            self.inner.insert(start + offset, link);
+        }
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    ///
+    /// A key that is present in multiple maps is only counted once. Since
+    /// this needs to check each entry for shadowing, it runs in O(N * M),
+    /// with `N` the number of maps and `M` the total number of entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// assert_eq!(chain.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.keys().count()
+    }
+
+    /// Returns `true` if the unified view of the chain contains no entries.
+    ///
+    /// Entries in disabled maps, or hidden by a tombstone, are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain = ChainMap::new();
+    /// chain.push_map(HashMap::new());
+    /// assert!(chain.is_empty());
+    ///
+    /// let mut hash = HashMap::new();
+    /// hash.insert("key", "value");
+    /// chain.push_map(hash);
+    /// assert!(!chain.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns the total number of entries across all maps in the chain.
+    ///
+    /// Unlike [`len`], keys that are present in multiple maps are counted
+    /// once for each map that contains them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// assert_eq!(chain.total_len(), 3);
+    /// ```
+    ///
+    /// [`len`]: #method.len
+    pub fn total_len(&self) -> usize {
+        self.inner.iter().map(|link| link.map.len()).sum()
+    }
+
+    /// Returns the number of entries in each map of the chain, in precedence
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// assert_eq!(chain.layer_sizes(), vec![1, 2]);
+    /// ```
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        self.inner.iter().map(|link| link.map.len()).collect()
+    }
+
+    /// Gathers summary statistics about the chain in a single pass.
+    ///
+    /// The returned [`Stats`] holds the number of entries in each map, the
+    /// number of unique keys in the unified view, which is the same as
+    /// [`len`], and the number of entries that are hidden by a
+    /// higher-precedence map, either because it contains the same key or
+    /// because it has a tombstone for it. Entries in disabled maps are
+    /// included in the map sizes, but are neither unique nor shadowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let stats = chain.stats();
+    ///
+    /// assert_eq!(stats.layer_sizes(), &[1, 2]);
+    /// assert_eq!(stats.unique_keys(), 2);
+    /// assert_eq!(stats.shadowed_entries(), 1);
+    /// ```
+    ///
+    /// [`Stats`]: struct.Stats.html
+    /// [`len`]: #method.len
+    pub fn stats(&self) -> Stats {
+        let mut hidden = HashSet::new();
+        let mut layer_sizes = Vec::with_capacity(self.inner.len());
+        let mut unique_keys = 0;
+        let mut shadowed_entries = 0;
+
+        for link in &self.inner {
+            layer_sizes.push(link.map.len());
+            if !link.enabled {
+                continue;
+            }
+            for key in link.map.keys() {
+                if hidden.insert(key) {
+                    unique_keys += 1;
+                } else {
+                    shadowed_entries += 1;
+                }
+            }
+            // Tombstones only hide entries in later maps
+            if let Some(tombstones) = &link.tombstones {
+                hidden.extend(tombstones);
+            }
+        }
+
+        Stats {
+            layer_sizes,
+            unique_keys,
+            shadowed_entries,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order.
+    ///
+    /// Each key is yielded exactly once, paired with its highest-precedence
+    /// value. Entries that are shadowed by a map earlier in the chain are
+    /// skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut entries: Vec<_> = chain.iter().collect();
+    /// entries.sort();
+    /// assert_eq!(entries, vec![(&"first", &1), (&"second", &2)]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V, S> {
+        Iter {
+            maps: &self.inner,
+            rest: &[],
+            index: 0,
+            current: None,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order, with mutable references to the values.
+    ///
+    /// Each key is yielded exactly once, paired with a mutable reference to
+    /// its highest-precedence value. Entries that are shadowed by a map
+    /// earlier in the chain are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// for (_, value) in chain.iter_mut() {
+    ///     *value *= 10;
+    /// }
+    ///
+    /// assert_eq!(chain.get("first"), Some(&10));
+    /// assert_eq!(chain.get("second"), Some(&20));
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, S> {
+        IterMut {
+            maps: self.inner.iter_mut(),
+            current: None,
+            tombstones: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// An iterator visiting all distinct keys in the chain, in arbitrary
+    /// order.
+    ///
+    /// A key that is present in multiple maps is only yielded once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut keys: Vec<_> = chain.keys().collect();
+    /// keys.sort();
+    /// assert_eq!(keys, vec![&"first", &"second"]);
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V, S> {
+        Keys { inner: self.iter() }
+    }
+
+    /// An iterator visiting the highest-precedence value for each key in the
+    /// chain, in arbitrary order.
+    ///
+    /// Values that are shadowed by a map earlier in the chain are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut values: Vec<_> = chain.values().collect();
+    /// values.sort();
+    /// assert_eq!(values, vec![&1, &2]);
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V, S> {
+        Values { inner: self.iter() }
+    }
+
+    /// An iterator visiting the key-value pairs in the unified view whose
+    /// keys start with the given prefix, in arbitrary order.
+    ///
+    /// Each key appears once, with its highest-precedence value. This scans
+    /// every entry in the chain, so it runs in the same time as [`iter`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert("db.host", "db.internal");
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("db.host", "localhost");
+    /// defaults.insert("db.port", "5432");
+    /// defaults.insert("dbg", "false");
+    ///
+    /// let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+    ///
+    /// let mut db: Vec<_> = chain.iter_prefix("db.").collect();
+    /// db.sort();
+    /// assert_eq!(db, vec![(&"db.host", &"db.internal"), (&"db.port", &"5432")]);
+    /// ```
+    ///
+    /// [`iter`]: #method.iter
+    pub fn iter_prefix<'a, 'p>(&'a self, prefix: &'p str) -> PrefixIter<'a, 'p, K, V, S>
+    where
+        K: Borrow<str>,
+    {
+        PrefixIter {
+            inner: self.iter(),
+            prefix,
+        }
+    }
+
+    /// An iterator visiting the distinct keys in the chain that start with
+    /// the given prefix, in arbitrary order.
+    ///
+    /// See [`iter_prefix`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("log.level".to_string(), 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("log.level".to_string(), 2);
+    /// second_map.insert("log.file".to_string(), 2);
+    /// second_map.insert("net.port".to_string(), 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut keys: Vec<_> = chain.keys_with_prefix("log.").collect();
+    /// keys.sort();
+    /// assert_eq!(keys, vec!["log.file", "log.level"]);
+    /// ```
+    ///
+    /// [`iter_prefix`]: #method.iter_prefix
+    pub fn keys_with_prefix<'a, 'p>(&'a self, prefix: &'p str) -> PrefixKeys<'a, 'p, K, V, S>
+    where
+        K: Borrow<str>,
+    {
+        PrefixKeys {
+            inner: self.iter_prefix(prefix),
+        }
+    }
+
+    /// An iterator visiting every entry in every map of the chain, including
+    /// entries that are shadowed by higher-precedence maps.
+    ///
+    /// Each item is a tuple of the index of the map in the chain, the key,
+    /// and the value. Maps are visited in precedence order, while the entries
+    /// within a single map are visited in arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let entries: Vec<_> = chain.iter_all().collect();
+    /// assert_eq!(entries, vec![(0, &"first", &1), (1, &"first", &2)]);
+    /// ```
+    pub fn iter_all(&self) -> IterAll<'_, K, V, S> {
+        IterAll {
+            maps: self.inner.iter().enumerate(),
+            current: None,
+        }
+    }
+
+    /// An iterator visiting every entry that is hidden by a higher-precedence
+    /// map, either because that map contains the same key or because it has
+    /// a tombstone for it.
+    ///
+    /// Each item is a tuple of the key, the index of the first enabled map
+    /// that hides it, and the index of the map holding the shadowed entry.
+    /// The hiding map is the one that provides the highest-precedence value
+    /// for the key, unless the key is deleted by a tombstone. A key present
+    /// in three maps will be yielded twice, once for each shadowed entry.
+    /// Disabled maps are skipped, so their entries are never yielded and they
+    /// never hide entries in other maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let shadowed: Vec<_> = chain.shadowed_entries().collect();
+    /// assert_eq!(shadowed, vec![(&"first", 0, 1)]);
+    /// ```
+    pub fn shadowed_entries(&self) -> ShadowedEntries<'_, K, V, S> {
+        ShadowedEntries {
+            maps: &self.inner,
+            entries: self.iter_all(),
+        }
+    }
+
+    /// An iterator visiting each distinct key that has at least one entry
+    /// hidden by a higher-precedence map, in arbitrary order.
+    ///
+    /// These are the keys for which at least one value has no effect on the
+    /// unified view, because it is shadowed by a higher-precedence map or
+    /// deleted by a tombstone. As with [`shadowed_entries`], disabled maps
+    /// are skipped. Use [`shadowed_entries`] to find which maps are involved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let mut third_map = HashMap::new();
+    /// third_map.insert("first", 3);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+    ///
+    /// let shadowed: Vec<_> = chain.shadowed_keys().collect();
+    /// assert_eq!(shadowed, vec![&"first"]);
+    /// ```
+    ///
+    /// [`shadowed_entries`]: #method.shadowed_entries
+    pub fn shadowed_keys(&self) -> ShadowedKeys<'_, K, V, S> {
+        ShadowedKeys {
+            inner: self.shadowed_entries(),
+        }
+    }
+
+    // The maps that may provide a value for the given key: every map up to
+    // and including the first one with a tombstone for the key.
+    fn visible_links<Q>(&self, k: &Q) -> &[Link<K, V, S>]
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        visible_links(&self.inner, k)
+    }
+
+    /// An iterator over the differences between the unified views of this
+    /// chain and `other`, in arbitrary order.
+    ///
+    /// `self` is treated as the earlier state and `other` as the later one:
+    /// keys only present in `other` are reported as [`Change::Added`], keys
+    /// only present in `self` as [`Change::Removed`], and keys whose
+    /// highest-precedence value differs as [`Change::Changed`]. Only the
+    /// resolved values are compared, so changes to shadowed entries are not
+    /// reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::{ChainMap, Change};
+    ///
+    /// let mut before_map = HashMap::new();
+    /// before_map.insert("kept", 1);
+    /// before_map.insert("changed", 1);
+    /// before_map.insert("removed", 1);
+    ///
+    /// let mut after_map = HashMap::new();
+    /// after_map.insert("kept", 1);
+    /// after_map.insert("changed", 2);
+    /// after_map.insert("added", 2);
+    ///
+    /// let mut before: ChainMap<_, _> = ChainMap::new();
+    /// before.push_map(before_map);
+    ///
+    /// let mut after: ChainMap<_, _> = ChainMap::new();
+    /// after.push_map(after_map);
+    ///
+    /// let mut changes: Vec<_> = before.diff(&after).collect();
+    /// changes.sort_by_key(|change| *change.key());
+    ///
+    /// assert_eq!(
+    ///     changes,
+    ///     vec![
+    ///         Change::Added { key: &"added", value: &2 },
+    ///         Change::Changed { key: &"changed", old: &1, new: &2 },
+    ///         Change::Removed { key: &"removed", value: &1 },
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`Change::Added`]: enum.Change.html#variant.Added
+    /// [`Change::Removed`]: enum.Change.html#variant.Removed
+    /// [`Change::Changed`]: enum.Change.html#variant.Changed
+    pub fn diff<'a>(&'a self, other: &'a ChainMap<K, V, S>) -> Diff<'a, K, V, S>
+    where
+        V: PartialEq,
+    {
+        Diff {
+            old: self,
+            new: other,
+            old_iter: self.iter(),
+            new_iter: other.iter(),
+        }
+    }
+
+    /// Returns `true` if the unified views of this chain and `other` contain
+    /// the same keys with equal highest-precedence values.
+    ///
+    /// The `==` operator compares the structure of the chains: the maps in
+    /// order, along with their names, priorities, tombstones and enabled
+    /// state. Two chains that resolve every key the same way compare unequal
+    /// with `==` if their entries are spread across the maps differently, but
+    /// are equal according to this method. Shadowed entries are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert("port", 9000);
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("port", 8080);
+    /// defaults.insert("workers", 4);
+    ///
+    /// let layered: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+    ///
+    /// let mut flattened: ChainMap<_, _> = ChainMap::new();
+    /// flattened.push_map(layered.flatten());
+    ///
+    /// assert!(layered != flattened);
+    /// assert!(layered.eq_resolved(&flattened));
+    /// ```
+    pub fn eq_resolved(&self, other: &ChainMap<K, V, S>) -> bool
+    where
+        V: PartialEq,
+    {
+        self.iter()
+            .all(|(key, value)| other.get(key) == Some(value))
+            && other.keys().all(|key| self.contains_key(key))
+    }
+
+    /// An iterator over the keys of map `b` that are also defined in map `a`,
+    /// in arbitrary order.
+    ///
+    /// Each item is a tuple of the key, its value in map `a` and its value in
+    /// map `b`. When `a` comes before `b` in the chain, these are the entries
+    /// of `b` that are overridden by `a`; comparing the two values shows
+    /// which overrides actually change the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert("same", 1);
+    /// overrides.insert("different", 2);
+    ///
+    /// let mut defaults = HashMap::new();
+    /// defaults.insert("same", 1);
+    /// defaults.insert("different", 1);
+    /// defaults.insert("default_only", 1);
+    ///
+    /// let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+    ///
+    /// let differing: Vec<_> = chain
+    ///     .diff_layers(0, 1)
+    ///     .filter(|&(_, a, b)| a != b)
+    ///     .collect();
+    /// assert_eq!(differing, vec![(&"different", &2, &1)]);
+    /// ```
+    pub fn diff_layers(&self, a: usize, b: usize) -> LayerDiff<'_, K, V, S> {
+        LayerDiff {
+            overriding: &self.inner[a].map,
+            overridden: self.inner[b].map.iter(),
+        }
+    }
+}
+
+impl<K, V, S> ChainMap<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone + Default,
+{
+    /// Creates a new `ChainMap` with a new empty map at the
+    /// highest-precedence end, followed by copies of all the maps in this
+    /// chain.
+    ///
+    /// This mirrors `new_child` from Python's `ChainMap`, allowing new
+    /// entries to be scoped to the child without affecting the parent. To
+    /// push a new scope in place without copying the chain, use
+    /// [`push_front_map`] with an empty map instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut globals = HashMap::new();
+    /// globals.insert("x", 1);
+    ///
+    /// let mut parent = ChainMap::new();
+    /// parent.push_map(globals);
+    ///
+    /// let mut child = parent.new_child();
+    /// child.insert("x", 2);
+    ///
+    /// assert_eq!(child["x"], 2);
+    /// assert_eq!(parent["x"], 1);
+    /// ```
+    ///
+    /// [`push_front_map`]: #method.push_front_map
+    pub fn new_child(&self) -> Self {
+        let mut inner = Links::with_capacity(self.inner.len() + 1);
+        inner.push(HashMap::default().into());
+        inner.extend(self.inner.iter().cloned());
+        ChainMap {
+            inner,
+            precedence: self.precedence,
+        }
+    }
+
+    /// Creates a new `ChainMap` containing copies of all the maps in this
+    /// chain except for the highest-precedence one.
+    ///
+    /// This mirrors the `parents` property of Python's `ChainMap`, and is
+    /// useful for resolving lookups in an enclosing scope. If the chain is
+    /// empty, the returned chain is also empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut inner = HashMap::new();
+    /// inner.insert("x", 2);
+    ///
+    /// let mut outer = HashMap::new();
+    /// outer.insert("x", 1);
+    ///
+    /// let chain: ChainMap<_, _> = vec![inner, outer].into_iter().collect();
+    ///
+    /// assert_eq!(chain["x"], 2);
+    /// assert_eq!(chain.parents()["x"], 1);
+    /// ```
+    pub fn parents(&self) -> Self {
+        ChainMap {
+            inner: self.inner.iter().skip(1).cloned().collect(),
+            precedence: self.precedence,
+        }
+    }
+
+    /// Copies the unified view of the chain into a single `HashMap`.
+    ///
+    /// The resulting map contains each key in the chain exactly once,
+    /// associated with its highest-precedence value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    /// let flat = chain.flatten();
+    ///
+    /// assert_eq!(flat.len(), 2);
+    /// assert_eq!(flat["first"], 1);
+    /// assert_eq!(flat["second"], 2);
+    /// ```
+    pub fn flatten(&self) -> HashMap<K, V, S> {
+        self.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Captures the current unified view of the chain in a [`Snapshot`].
+    ///
+    /// The snapshot is immutable and shares its contents through an [`Arc`],
+    /// so it is cheap to clone and can be sent to other threads. Later
+    /// changes to the chain are not reflected in the snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<_, _> = ChainMap::new();
+    /// chain.insert("level", 1);
+    ///
+    /// let snapshot = chain.snapshot();
+    /// chain.insert("level", 2);
+    ///
+    /// let reader = snapshot.clone();
+    /// let level = thread::spawn(move || reader["level"]).join().unwrap();
+    ///
+    /// assert_eq!(level, 1);
+    /// assert_eq!(snapshot.get("level"), Some(&1));
+    /// assert_eq!(chain["level"], 2);
+    /// ```
+    ///
+    /// [`Snapshot`]: struct.Snapshot.html
+    /// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+    pub fn snapshot(&self) -> Snapshot<K, V, S> {
+        Snapshot {
+            inner: Arc::new(self.flatten()),
+        }
+    }
+}
+
+impl<K, V, S> Default for ChainMap<K, V, S> {
+    fn default() -> Self {
+        ChainMap {
+            inner: Links::new(),
+            precedence: Precedence::default(),
+        }
+    }
+}
+
+impl<K, V, S> Index<usize> for ChainMap<K, V, S> {
+    type Output = HashMap<K, V, S>;
+
+    /// Returns a reference to the map at the given index in the chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    fn index(&self, index: usize) -> &HashMap<K, V, S> {
+        &self.inner[index].map
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for ChainMap<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<K, Q, V, S> IndexMut<&Q> for ChainMap<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+{
+    /// Returns a mutable reference to the highest-precedence value associated
+    /// with the given key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the chain.
+    fn index_mut(&mut self, k: &Q) -> &mut V {
+        self.get_mut(k).expect("no entry found for key")
+    }
+}
+
+impl<K, V, S> FromIterator<HashMap<K, V, S>> for ChainMap<K, V, S> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = HashMap<K, V, S>>,
+    {
+        ChainMap {
+            inner: iter.into_iter().map(Link::from).collect(),
+            precedence: Precedence::default(),
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Creates a chain with a single map holding the key-value pairs.
+    ///
+    /// The chain always has exactly one map, even if the iterator is empty.
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        ChainMap::from(iter.into_iter().collect::<HashMap<K, V, S>>())
+    }
+}
+
+impl<K, V, S> From<HashMap<K, V, S>> for ChainMap<K, V, S> {
+    /// Creates a chain containing only the given map.
+    fn from(map: HashMap<K, V, S>) -> Self {
+        iter::once(map).collect()
+    }
+}
+
+impl<K, V, S> From<Vec<HashMap<K, V, S>>> for ChainMap<K, V, S> {
+    /// Creates a chain from a list of maps in precedence order, so the first
+    /// map has the highest precedence.
+    fn from(maps: Vec<HashMap<K, V, S>>) -> Self {
+        maps.into_iter().collect()
+    }
+}
+
+// Items that use const generics are wrapped in `const_generics!`, so they
+// are only parsed when the `const-generics` feature is enabled and the rest
+// of this file still builds with the minimum supported Rust version.
+#[cfg(feature = "const-generics")]
+const_generics! {
+    impl<K, V, const N: usize, S> From<[HashMap<K, V, S>; N]> for ChainMap<K, V, S> {
+        /// Creates a chain from an array of maps in precedence order, so the
+        /// first map has the highest precedence.
+        ///
+        /// This implementation is only available with the `const-generics`
+        /// feature, which requires Rust 1.59 or later.
+        fn from(maps: [HashMap<K, V, S>; N]) -> Self {
+            IntoIterator::into_iter(maps).collect()
+        }
+    }
+
+    impl<K, V, S> ChainMap<K, V, S>
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+    {
+        /// Returns the highest-precedence value associated with each key in an
+        /// array of keys, in the same order as the keys.
+        ///
+        /// As with [`get_many_slice`], the chain is walked once for the whole
+        /// batch. This method is only available with the `const-generics`
+        /// feature, which requires Rust 1.59 or later.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # #[cfg(feature = "const-generics")]
+        /// # {
+        /// use std::collections::HashMap;
+        /// use chain_map::ChainMap;
+        ///
+        /// let mut overrides = HashMap::new();
+        /// overrides.insert("port", 9000);
+        ///
+        /// let mut defaults = HashMap::new();
+        /// defaults.insert("port", 8080);
+        /// defaults.insert("workers", 4);
+        ///
+        /// let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+        ///
+        /// let [port, workers, timeout] = chain.get_many(["port", "workers", "timeout"]);
+        /// assert_eq!(port, Some(&9000));
+        /// assert_eq!(workers, Some(&4));
+        /// assert_eq!(timeout, None);
+        /// # }
+        /// ```
+        ///
+        /// [`get_many_slice`]: #method.get_many_slice
+        pub fn get_many<Q, const M: usize>(&self, keys: [&Q; M]) -> [Option<&V>; M]
+        where
+            K: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            let mut values = [None; M];
+            self.resolve_many(&keys, &mut values);
+            values
+        }
+    }
+}
+
+impl<K, V, S> From<ChainMap<K, V, S>> for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Consumes the chain, collecting its unified view into a single map.
+    ///
+    /// Each key is kept once, with its highest-precedence value, as in
+    /// [`ChainMap::flatten`], but the keys and values are moved rather than
+    /// cloned.
+    ///
+    /// [`ChainMap::flatten`]: struct.ChainMap.html#method.flatten
+    fn from(chain: ChainMap<K, V, S>) -> Self {
+        chain.into_iter().collect()
+    }
+}
+
+impl<K, V, S> Extend<HashMap<K, V, S>> for ChainMap<K, V, S> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = HashMap<K, V, S>>,
+    {
+        for map in iter {
+            self.push_link(map.into());
+        }
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Inserts each key-value pair into the highest-precedence map in the
+    /// chain, as with [`insert`], adding a new empty map first if the chain
+    /// is empty.
+    ///
+    /// [`insert`]: struct.ChainMap.html#method.insert
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> IntoIterator for ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, S>;
+
+    /// Creates a consuming iterator visiting each key in the chain exactly
+    /// once, paired with its highest-precedence value, in arbitrary order.
+    ///
+    /// Entries that are shadowed by a map earlier in the chain are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut entries: Vec<_> = chain.into_iter().collect();
+    /// entries.sort();
+    /// assert_eq!(entries, vec![("first", 1), ("second", 2)]);
+    /// ```
+    fn into_iter(self) -> IntoIter<K, V, S> {
+        IntoIter {
+            maps: self.inner.into_iter(),
+            current: None,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, S>;
+
+    fn into_iter(self) -> Iter<'a, K, V, S> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut ChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, S>;
+
+    fn into_iter(self) -> IterMut<'a, K, V, S> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> Debug for ChainMap<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainMap")
+            .field("inner", &self.layers().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Compares the precedence of the chains and the maps in them, along with
+/// their names, priorities, tombstones and enabled state. To compare only the
+/// unified views, use [`ChainMap::eq_resolved`].
+///
+/// [`ChainMap::eq_resolved`]: struct.ChainMap.html#method.eq_resolved
+impl<K, V, S> PartialEq for ChainMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &ChainMap<K, V, S>) -> bool {
+        self.precedence == other.precedence && self.inner.eq(&other.inner)
+    }
+}
+
+impl<K, V, S> Eq for ChainMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+/// Hashes the precedence of the chain and the maps in it, along with their
+/// names, priorities, tombstones and enabled state, consistent with the
+/// `PartialEq` implementation.
+impl<K, V, S> Hash for ChainMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.precedence.hash(state);
+        self.inner[..].hash(state);
+    }
+}
+
+/// Identifies a single map in a `ChainMap`, by its index in the chain and its
+/// name, if it has one.
+///
+/// This `struct` is returned by the [`get_with_source`] method on
+/// [`ChainMap`]. Its `Display` implementation shows the name of the map, or
+/// its index if the map has no name.
+///
+/// [`get_with_source`]: struct.ChainMap.html#method.get_with_source
+/// [`ChainMap`]: struct.ChainMap.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayerId<'a> {
+    index: usize,
+    name: Option<&'a str>,
+}
+
+impl<'a> LayerId<'a> {
+    /// Returns the index of the map in the chain.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the name of the map, if it has one.
+    pub fn name(&self) -> Option<&'a str> {
+        self.name
+    }
+}
+
+impl<'a> Display for LayerId<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => f.write_str(name),
+            None => write!(f, "layer {}", self.index),
+        }
+    }
+}
+
+/// Summary statistics about the maps in a `ChainMap`.
+///
+/// This `struct` is created by the [`stats`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`stats`]: struct.ChainMap.html#method.stats
+/// [`ChainMap`]: struct.ChainMap.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Stats {
+    layer_sizes: Vec<usize>,
+    unique_keys: usize,
+    shadowed_entries: usize,
+}
+
+impl Stats {
+    /// Returns the number of entries in each map, in precedence order.
+    pub fn layer_sizes(&self) -> &[usize] {
+        &self.layer_sizes
+    }
+
+    /// Returns the total number of entries across all maps, including
+    /// shadowed entries.
+    pub fn total_entries(&self) -> usize {
+        self.layer_sizes.iter().sum()
+    }
+
+    /// Returns the number of unique keys in the unified view.
+    pub fn unique_keys(&self) -> usize {
+        self.unique_keys
+    }
+
+    /// Returns the number of entries that are shadowed by a map earlier in
+    /// the chain.
+    pub fn shadowed_entries(&self) -> usize {
+        self.shadowed_entries
+    }
+}
+
+/// A guard for a temporary map at the front of a `ChainMap`.
+///
+/// This `struct` is created by the [`push_scope`] method on [`ChainMap`]. The
+/// map is removed from the chain when the guard is dropped. See its
+/// documentation for more.
+///
+/// [`push_scope`]: struct.ChainMap.html#method.push_scope
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Scope<'a, K, V, S> {
+    chain: &'a mut ChainMap<K, V, S>,
+}
+
+impl<'a, K, V, S> Deref for Scope<'a, K, V, S> {
+    type Target = ChainMap<K, V, S>;
+
+    fn deref(&self) -> &ChainMap<K, V, S> {
+        self.chain
+    }
+}
+
+impl<'a, K, V, S> DerefMut for Scope<'a, K, V, S> {
+    fn deref_mut(&mut self) -> &mut ChainMap<K, V, S> {
+        self.chain
+    }
+}
+
+impl<'a, K, V, S> Drop for Scope<'a, K, V, S> {
+    fn drop(&mut self) {
+        if !self.chain.inner.is_empty() {
+            self.chain.inner.remove(0);
+        }
+    }
+}
+
+/// A set of staged changes to a `ChainMap`.
+///
+/// This `struct` is created by the [`begin_transaction`] method on
+/// [`ChainMap`]. It dereferences to the chain with the staged changes
+/// applied, so all of the read methods of [`ChainMap`] can be used to
+/// inspect the result before committing. Dropping the transaction without
+/// calling [`commit`] discards the changes. See the documentation of
+/// [`begin_transaction`] for more.
+///
+/// [`begin_transaction`]: struct.ChainMap.html#method.begin_transaction
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`commit`]: #method.commit
+pub struct Transaction<'a, K, V, S> {
+    chain: &'a mut ChainMap<K, V, S>,
+    finished: bool,
+}
+
+impl<'a, K, V, S> Transaction<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Stages an insertion of the key and value, returning the value that
+    /// was previously staged for the key, if any.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.chain.insert(k, v)
+    }
+
+    /// Stages the deletion of the key, so it will be removed from the unified
+    /// view of the chain when the transaction is committed.
+    ///
+    /// On commit, the deletion is recorded as a tombstone in the
+    /// highest-precedence map. See [`ChainMap::insert_tombstone`] for more.
+    ///
+    /// [`ChainMap::insert_tombstone`]: struct.ChainMap.html#method.insert_tombstone
+    pub fn remove(&mut self, k: K) {
+        self.chain.insert_tombstone(k)
+    }
+
+    /// Applies the staged changes to the highest-precedence map of the
+    /// chain.
+    pub fn commit(mut self) {
+        self.finished = true;
+        let staged = self.chain.inner.remove(0);
+
+        // Deletions are applied first, so a key that was deleted and then
+        // inserted again keeps its new value
+        for k in staged.tombstones.into_iter().flatten() {
+            self.chain.insert_tombstone(k);
+        }
+        for (k, v) in staged.map {
+            self.chain.insert(k, v);
+        }
+    }
+
+    /// Discards the staged changes, leaving the chain unchanged.
+    pub fn rollback(self) {}
+}
+
+impl<'a, K, V, S> Deref for Transaction<'a, K, V, S> {
+    type Target = ChainMap<K, V, S>;
+
+    fn deref(&self) -> &ChainMap<K, V, S> {
+        self.chain
+    }
+}
+
+impl<'a, K, V, S> Drop for Transaction<'a, K, V, S> {
+    fn drop(&mut self) {
+        if !self.finished && !self.chain.inner.is_empty() {
+            self.chain.inner.remove(0);
+        }
+    }
+}
+
+/// An immutable, shareable copy of the unified view of a `ChainMap`.
+///
+/// This `struct` is created by the [`snapshot`] method on [`ChainMap`]. It
+/// dereferences to a [`HashMap`] holding the highest-precedence value for
+/// each key, and cloning it only clones a reference to the same map.
+///
+/// [`snapshot`]: struct.ChainMap.html#method.snapshot
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+pub struct Snapshot<K, V, S = RandomState> {
+    inner: Arc<HashMap<K, V, S>>,
+}
+
+impl<K, V, S> Clone for Snapshot<K, V, S> {
+    fn clone(&self) -> Self {
+        Snapshot {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K, V, S> Deref for Snapshot<K, V, S> {
+    type Target = HashMap<K, V, S>;
+
+    fn deref(&self) -> &HashMap<K, V, S> {
+        &self.inner
+    }
+}
+
+impl<K, V, S> Debug for Snapshot<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// An iterator over the entries of a `ChainMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`iter`]: struct.ChainMap.html#method.iter
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Iter<'a, K, V, S> {
+    maps: &'a [Link<K, V, S>],
+    // The maps of a second chain that follow `maps`, for views created by
+    // `chained_with`. Indexes run across both slices.
+    rest: &'a [Link<K, V, S>],
+    index: usize,
+    current: Option<hash_map::Iter<'a, K, V>>,
+}
+
+impl<'a, K, V, S> Iter<'a, K, V, S> {
+    fn link(&self, index: usize) -> Option<&'a Link<K, V, S>> {
+        match self.maps.get(index) {
+            Some(link) => Some(link),
+            None => self.rest.get(index - self.maps.len()),
+        }
+    }
+}
+
+impl<'a, K, V, S> Clone for Iter<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        Iter {
+            maps: self.maps,
+            rest: self.rest,
+            index: self.index,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Iter<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let shadowing = self.maps.iter().chain(self.rest).take(self.index);
+                let entry =
+                    current.find(|&(key, _)| !shadowing.clone().any(|link| link.hides(key)));
+                if entry.is_some() {
+                    return entry;
+                }
+                self.index += 1;
+            }
+            let link = self.link(self.index)?;
+            if link.enabled {
+                self.current = Some(link.map.iter());
+            } else {
+                self.current = None;
+                self.index += 1;
+            }
+        }
+    }
+}
+
+/// A mutable iterator over the entries of a `ChainMap`.
+///
+/// This `struct` is created by the [`iter_mut`] method on [`ChainMap`]. See
+/// its documentation for more.
+///
+/// [`iter_mut`]: struct.ChainMap.html#method.iter_mut
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct IterMut<'a, K, V, S> {
+    maps: slice::IterMut<'a, Link<K, V, S>>,
+    current: Option<hash_map::IterMut<'a, K, V>>,
+    // The tombstones of the current map only hide keys in later maps, so they
+    // are added to `seen` once the current map is exhausted.
+    tombstones: Option<&'a HashSet<K>>,
+    // The maps that have already been visited are mutably borrowed, so the
+    // keys they contained are tracked here to find shadowed entries.
+    seen: HashSet<&'a K>,
+}
+
+impl<'a, K, V, S> Iterator for IterMut<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let seen = &mut self.seen;
+                let entry = current.find(|entry| seen.insert(entry.0));
+                if entry.is_some() {
+                    return entry;
+                }
+            }
+            // Keys deleted by the previous map are hidden in every later map
+            if let Some(tombstones) = self.tombstones.take() {
+                self.seen.extend(tombstones);
+            }
+            let Link {
+                map,
+                tombstones,
+                enabled,
+                ..
+            } = self.maps.next()?;
+            if !*enabled {
+                continue;
+            }
+            let tombstones: &'a Option<HashSet<K>> = tombstones;
+            self.tombstones = tombstones.as_ref();
+            self.current = Some(map.iter_mut());
+        }
+    }
+}
+
+/// An iterator over the distinct keys of a `ChainMap`.
+///
+/// This `struct` is created by the [`keys`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`keys`]: struct.ChainMap.html#method.keys
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Keys<'a, K, V, S> {
+    inner: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for Keys<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Keys<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+/// An iterator over the entries of a `ChainMap` whose keys start with a
+/// prefix.
+///
+/// This `struct` is created by the [`iter_prefix`] method on [`ChainMap`].
+/// See its documentation for more.
+///
+/// [`iter_prefix`]: struct.ChainMap.html#method.iter_prefix
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct PrefixIter<'a, 'p, K, V, S> {
+    inner: Iter<'a, K, V, S>,
+    prefix: &'p str,
+}
+
+impl<'a, 'p, K, V, S> Clone for PrefixIter<'a, 'p, K, V, S> {
+    fn clone(&self) -> Self {
+        PrefixIter {
+            inner: self.inner.clone(),
+            prefix: self.prefix,
+        }
+    }
+}
+
+impl<'a, 'p, K, V, S> Iterator for PrefixIter<'a, 'p, K, V, S>
+where
+    K: Hash + Eq + Borrow<str>,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let prefix = self.prefix;
+        self.inner
+            .find(|&(key, _)| Borrow::<str>::borrow(key).starts_with(prefix))
+    }
+}
+
+/// An iterator over the distinct keys of a `ChainMap` that start with a
+/// prefix.
+///
+/// This `struct` is created by the [`keys_with_prefix`] method on
+/// [`ChainMap`]. See its documentation for more.
+///
+/// [`keys_with_prefix`]: struct.ChainMap.html#method.keys_with_prefix
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct PrefixKeys<'a, 'p, K, V, S> {
+    inner: PrefixIter<'a, 'p, K, V, S>,
+}
+
+impl<'a, 'p, K, V, S> Clone for PrefixKeys<'a, 'p, K, V, S> {
+    fn clone(&self) -> Self {
+        PrefixKeys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, 'p, K, V, S> Iterator for PrefixKeys<'a, 'p, K, V, S>
+where
+    K: Hash + Eq + Borrow<str>,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+/// An iterator over the resolved values of a `ChainMap`.
+///
+/// This `struct` is created by the [`values`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`values`]: struct.ChainMap.html#method.values
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Values<'a, K, V, S> {
+    inner: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for Values<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Values<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+}
+
+/// An iterator over every entry of every map in a `ChainMap`.
+///
+/// This `struct` is created by the [`iter_all`] method on [`ChainMap`]. See
+/// its documentation for more.
+///
+/// [`iter_all`]: struct.ChainMap.html#method.iter_all
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct IterAll<'a, K, V, S> {
+    maps: Enumerate<slice::Iter<'a, Link<K, V, S>>>,
+    current: Option<(usize, hash_map::Iter<'a, K, V>)>,
+}
+
+impl<'a, K, V, S> Clone for IterAll<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        IterAll {
+            maps: self.maps.clone(),
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for IterAll<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (usize, &'a K, &'a V);
+
+    fn next(&mut self) -> Option<(usize, &'a K, &'a V)> {
+        loop {
+            if let Some((index, current)) = self.current.as_mut() {
+                if let Some((key, value)) = current.next() {
+                    return Some((*index, key, value));
+                }
+            }
+            let (index, link) = self.maps.next()?;
+            self.current = Some((index, link.map.iter()));
+        }
+    }
+}
+
+/// An iterator over the shadowed entries of a `ChainMap`.
+///
+/// This `struct` is created by the [`shadowed_entries`] method on
+/// [`ChainMap`]. See its documentation for more.
+///
+/// [`shadowed_entries`]: struct.ChainMap.html#method.shadowed_entries
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ShadowedEntries<'a, K, V, S> {
+    maps: &'a [Link<K, V, S>],
+    entries: IterAll<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for ShadowedEntries<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ShadowedEntries {
+            maps: self.maps,
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for ShadowedEntries<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, usize, usize);
+
+    fn next(&mut self) -> Option<(&'a K, usize, usize)> {
+        let maps = self.maps;
+        self.entries.find_map(|(shadowed, key, _)| {
+            if !maps[shadowed].enabled {
+                return None;
+            }
+            maps[..shadowed]
+                .iter()
+                .position(|link| link.hides(key))
+                .map(|shadowing| (key, shadowing, shadowed))
+        })
+    }
+}
+
+/// An iterator over the keys of a `ChainMap` that are present in more than
+/// one map.
+///
+/// This `struct` is created by the [`shadowed_keys`] method on [`ChainMap`].
+/// See its documentation for more.
+///
+/// [`shadowed_keys`]: struct.ChainMap.html#method.shadowed_keys
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ShadowedKeys<'a, K, V, S> {
+    inner: ShadowedEntries<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for ShadowedKeys<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ShadowedKeys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for ShadowedKeys<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let maps = self.inner.maps;
+        // Only report each key for the first map that it is shadowed in
+        self.inner
+            .find(|&(key, shadowing, shadowed)| {
+                !maps[shadowing + 1..shadowed]
+                    .iter()
+                    .any(|link| link.provides(key))
+            })
+            .map(|(key, _, _)| key)
+    }
+}
+
+/// A single difference between the unified views of two `ChainMap`s.
+///
+/// This `enum` is yielded by the [`Diff`] iterator, created by the [`diff`]
+/// method on [`ChainMap`].
+///
+/// [`Diff`]: struct.Diff.html
+/// [`diff`]: struct.ChainMap.html#method.diff
+/// [`ChainMap`]: struct.ChainMap.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Change<'a, K, V> {
+    /// A key that is only present in the later chain.
+    Added {
+        /// The added key.
+        key: &'a K,
+        /// The value of the key in the later chain.
+        value: &'a V,
+    },
+    /// A key that is only present in the earlier chain.
+    Removed {
+        /// The removed key.
+        key: &'a K,
+        /// The value of the key in the earlier chain.
+        value: &'a V,
+    },
+    /// A key that is present in both chains with different values.
+    Changed {
+        /// The changed key.
+        key: &'a K,
+        /// The value of the key in the earlier chain.
+        old: &'a V,
+        /// The value of the key in the later chain.
+        new: &'a V,
+    },
+}
+
+impl<'a, K, V> Change<'a, K, V> {
+    /// Returns the key that this change applies to.
+    pub fn key(&self) -> &'a K {
+        match *self {
+            Change::Added { key, .. }
+            | Change::Removed { key, .. }
+            | Change::Changed { key, .. } => key,
+        }
+    }
+}
+
+/// An iterator over the differences between two `ChainMap`s.
+///
+/// This `struct` is created by the [`diff`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`diff`]: struct.ChainMap.html#method.diff
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Diff<'a, K, V, S> {
+    old: &'a ChainMap<K, V, S>,
+    new: &'a ChainMap<K, V, S>,
+    old_iter: Iter<'a, K, V, S>,
+    new_iter: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for Diff<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        Diff {
+            old: self.old,
+            new: self.new,
+            old_iter: self.old_iter.clone(),
+            new_iter: self.new_iter.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Diff<'a, K, V, S>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    type Item = Change<'a, K, V>;
+
+    fn next(&mut self) -> Option<Change<'a, K, V>> {
+        let new = self.new;
+        let removed_or_changed = self.old_iter.find_map(|(key, old)| match new.get(key) {
+            None => Some(Change::Removed { key, value: old }),
+            Some(value) if value != old => Some(Change::Changed {
+                key,
+                old,
+                new: value,
+            }),
+            Some(_) => None,
+        });
+
+        if removed_or_changed.is_some() {
+            return removed_or_changed;
+        }
+
+        let old = self.old;
+        self.new_iter
+            .find(|&(key, _)| !old.contains_key(key))
+            .map(|(key, value)| Change::Added { key, value })
+    }
+}
+
+/// An iterator over the keys shared by two maps in a `ChainMap`.
+///
+/// This `struct` is created by the [`diff_layers`] method on [`ChainMap`].
+/// See its documentation for more.
+///
+/// [`diff_layers`]: struct.ChainMap.html#method.diff_layers
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct LayerDiff<'a, K, V, S> {
+    overriding: &'a HashMap<K, V, S>,
+    overridden: hash_map::Iter<'a, K, V>,
+}
+
+impl<'a, K, V, S> Clone for LayerDiff<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        LayerDiff {
+            overriding: self.overriding,
+            overridden: self.overridden.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for LayerDiff<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V, &'a V)> {
+        let overriding = self.overriding;
+        self.overridden
+            .find_map(|(key, value)| overriding.get(key).map(|over| (key, over, value)))
+    }
+}
+
+/// An owning iterator over the resolved entries of a `ChainMap`.
+///
+/// This `struct` is created by the [`into_iter`] method on [`ChainMap`]
+/// (provided by the `IntoIterator` trait). See its documentation for more.
+///
+/// [`into_iter`]: struct.ChainMap.html#method.into_iter
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct IntoIter<K, V, S> {
+    maps: LinksIntoIter<K, V, S>,
+    current: Option<hash_map::IntoIter<K, V>>,
+}
+
+impl<K, V, S> Iterator for IntoIter<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            if let Some(entry) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(entry);
+            }
+            let link = self.maps.next()?;
+            if !link.enabled {
+                continue;
+            }
+            // Remove the shadowed entries from the rest of the chain before
+            // giving up ownership of the keys in this map.
+            for later in self.maps.as_mut_slice() {
+                later.map.retain(|key, _| !link.hides(key));
+            }
+            let map = link.map;
+            self.current = Some(map.into_iter());
+        }
+    }
+}
+
+/// An iterator over every value associated with a single key in a
+/// `ChainMap`.
+///
+/// This `struct` is created by the [`get_all`] method on [`ChainMap`]. See
+/// its documentation for more.
+///
+/// [`get_all`]: struct.ChainMap.html#method.get_all
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct GetAll<'a, K, V, S, Q: ?Sized> {
+    maps: slice::Iter<'a, Link<K, V, S>>,
+    key: &'a Q,
+}
+
+impl<'a, K, V, S, Q: ?Sized> Clone for GetAll<'a, K, V, S, Q> {
+    fn clone(&self) -> Self {
+        GetAll {
+            maps: self.maps.clone(),
+            key: self.key,
+        }
+    }
+}
+
+impl<'a, K, V, S, Q> Iterator for GetAll<'a, K, V, S, Q>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        let key = self.key;
+        self.maps.find_map(|link| link.lookup(key))
+    }
+}
+
+/// An iterator over the maps in a `ChainMap`.
+///
+/// This `struct` is created by the [`layers`] method on [`ChainMap`]. See its
+/// documentation for more.
+///
+/// [`layers`]: struct.ChainMap.html#method.layers
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct Layers<'a, K, V, S> {
+    inner: slice::Iter<'a, Link<K, V, S>>,
+}
+
+impl<'a, K, V, S> Clone for Layers<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        Layers {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> Iterator for Layers<'a, K, V, S> {
+    type Item = &'a HashMap<K, V, S>;
+
+    fn next(&mut self) -> Option<&'a HashMap<K, V, S>> {
+        self.inner.next().map(|link| &link.map)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for Layers<'a, K, V, S> {
+    fn next_back(&mut self) -> Option<&'a HashMap<K, V, S>> {
+        self.inner.next_back().map(|link| &link.map)
+    }
+}
+
+impl<'a, K, V, S> ExactSizeIterator for Layers<'a, K, V, S> {}
+
+/// A mutable iterator over the maps in a `ChainMap`.
+///
+/// This `struct` is created by the [`layers_mut`] method on [`ChainMap`]. See
+/// its documentation for more.
+///
+/// [`layers_mut`]: struct.ChainMap.html#method.layers_mut
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct LayersMut<'a, K, V, S> {
+    inner: slice::IterMut<'a, Link<K, V, S>>,
+}
+
+impl<'a, K, V, S> Iterator for LayersMut<'a, K, V, S> {
+    type Item = &'a mut HashMap<K, V, S>;
+
+    fn next(&mut self) -> Option<&'a mut HashMap<K, V, S>> {
+        self.inner.next().map(|link| &mut link.map)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for LayersMut<'a, K, V, S> {
+    fn next_back(&mut self) -> Option<&'a mut HashMap<K, V, S>> {
+        self.inner.next_back().map(|link| &mut link.map)
+    }
+}
+
+impl<'a, K, V, S> ExactSizeIterator for LayersMut<'a, K, V, S> {}
+
+/// A view into a single entry in a `ChainMap`, which may either be vacant or
+/// occupied.
+///
+/// This `enum` is constructed from the [`entry`] method on [`ChainMap`].
+///
+/// [`entry`]: struct.ChainMap.html#method.entry
+/// [`ChainMap`]: struct.ChainMap.html
+pub enum Entry<'a, K, V, S = RandomState> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S> {
+    /// Ensures a value is in the entry by inserting the default if empty, and
+    /// returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, u32> = ChainMap::new();
+    /// chain.entry("key").or_insert(12);
+    ///
+    /// assert_eq!(chain["key"], 12);
+    /// ```
+    pub fn or_insert(self, default: V) -> &'a mut V
+    where
+        K: Hash,
+        S: BuildHasher,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default
+    /// function if empty, and returns a mutable reference to the value in the
+    /// entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, String> = ChainMap::new();
+    /// chain.entry("key").or_insert_with(|| "value".to_string());
+    ///
+    /// assert_eq!(chain["key"], "value");
+    /// ```
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        K: Hash,
+        S: BuildHasher,
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns a reference to this entry's key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, u32> = ChainMap::new();
+    /// assert_eq!(chain.entry("key").key(), &"key");
+    /// ```
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, u32> = ChainMap::new();
+    ///
+    /// chain.entry("key").and_modify(|e| *e += 1).or_insert(42);
+    /// assert_eq!(chain["key"], 42);
+    ///
+    /// chain.entry("key").and_modify(|e| *e += 1).or_insert(42);
+    /// assert_eq!(chain["key"], 43);
+    /// ```
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K, V: Default, S> Entry<'a, K, V, S> {
+    /// Ensures a value is in the entry by inserting the default value if
+    /// empty, and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut chain: ChainMap<&str, Option<u32>> = ChainMap::new();
+    /// chain.entry("key").or_default();
+    ///
+    /// assert_eq!(chain["key"], None);
+    /// ```
+    pub fn or_default(self) -> &'a mut V
+    where
+        K: Hash,
+        S: BuildHasher,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(V::default()),
+        }
+    }
+}
+
+/// A view into an occupied entry in a `ChainMap`. It is part of the [`Entry`]
+/// enum.
+///
+/// The entry refers to the highest-precedence occurrence of the key in the
+/// chain.
+///
+/// [`Entry`]: enum.Entry.html
+pub struct OccupiedEntry<'a, K, V, S = RandomState> {
+    #[cfg(feature = "std")]
+    inner: hash_map::OccupiedEntry<'a, K, V>,
+    #[cfg(not(feature = "std"))]
+    inner: hash_map::OccupiedEntry<'a, K, V, S>,
+    marker: PhantomData<S>,
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+    /// Gets a reference to the key in the entry.
+    pub fn key(&self) -> &K {
+        self.inner.key()
+    }
+
+    /// Gets a reference to the value in the entry.
+    pub fn get(&self) -> &V {
+        self.inner.get()
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    ///
+    /// If you need a reference to the `OccupiedEntry` which may outlive the
+    /// destruction of the `Entry` value, see [`into_mut`].
+    ///
+    /// [`into_mut`]: #method.into_mut
+    pub fn get_mut(&mut self) -> &mut V {
+        self.inner.get_mut()
+    }
+
+    /// Converts the entry into a mutable reference to the value in the entry
+    /// with a lifetime bound to the chain itself.
+    pub fn into_mut(self) -> &'a mut V {
+        self.inner.into_mut()
+    }
+
+    /// Sets the value of the entry, and returns the entry's old value.
+    pub fn insert(&mut self, value: V) -> V {
+        self.inner.insert(value)
+    }
+
+    /// Takes the value out of the entry, and returns it.
+    ///
+    /// The key is only removed from the map that held this occurrence, so a
+    /// value for the same key in a lower-precedence map will become visible.
+    pub fn remove(self) -> V {
+        self.inner.remove()
+    }
+
+    /// Takes ownership of the key and value from the chain.
+    ///
+    /// As with [`remove`], a value for the same key in a lower-precedence map
+    /// will become visible.
+    ///
+    /// [`remove`]: #method.remove
+    pub fn remove_entry(self) -> (K, V) {
+        self.inner.remove_entry()
+    }
+}
+
+/// A view into a vacant entry in a `ChainMap`. It is part of the [`Entry`]
+/// enum.
+///
+/// [`Entry`]: enum.Entry.html
+pub struct VacantEntry<'a, K, V, S = RandomState> {
+    #[cfg(feature = "std")]
+    inner: hash_map::VacantEntry<'a, K, V>,
+    #[cfg(not(feature = "std"))]
+    inner: hash_map::VacantEntry<'a, K, V, S>,
+    marker: PhantomData<S>,
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the `VacantEntry`.
+    pub fn key(&self) -> &K {
+        self.inner.key()
+    }
+
+    /// Take ownership of the key.
+    pub fn into_key(self) -> K {
+        self.inner.into_key()
+    }
+
+    /// Sets the value of the entry in the highest-precedence map of the chain,
+    /// and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V
+    where
+        K: Hash,
+        S: BuildHasher,
+    {
+        self.inner.insert(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_map_adds_to_chain() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut chain = ChainMap::new();
+        chain.push_map(first_map);
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.get("second"), None);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        chain.push_map(second_map);
+
+        assert_eq!(chain.get("second"), Some(&2));
+    }
+
+    #[test]
+    fn contains_key_searches_all_maps() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+        assert!(chain.contains_key("first"));
+        assert!(chain.contains_key("second"));
+        assert!(chain.contains_key("third"));
+        assert!(!chain.contains_key("fourth"));
+    }
+
+    #[test]
+    fn get_follows_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 1);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.get("second"), Some(&2));
+        assert_eq!(chain.get("third"), Some(&3));
+        assert_eq!(chain.get("fourth"), None);
+    }
+
+    #[test]
+    fn index_follows_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 1);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain["first"], 1);
+        assert_eq!(chain["second"], 2);
+        assert_eq!(chain["third"], 3);
+    }
+
+    #[test]
+    fn index_by_position_returns_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        assert_eq!(chain[1]["first"], 2);
+        assert_eq!(chain.get_map(0), Some(&chain[0]));
+        assert_eq!(chain.get_map(2), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_by_position_panics_when_out_of_bounds() {
+        let chain: ChainMap<&str, i32> = ChainMap::new();
+
+        let _ = &chain[0];
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_panics_when_key_is_not_present() {
+        let chain: ChainMap<&str, i32> = ChainMap::new();
+
+        let _ = chain["notset"];
+    }
+
+    #[test]
+    fn index_mut_modifies_highest_precedence_value() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        chain["first"] = 10;
+        chain["second"] += 1;
+
+        assert_eq!(chain.get_from_layer(0, "first"), Some(&10));
+        assert_eq!(chain.get_from_layer(1, "first"), Some(&2));
+        assert_eq!(chain["second"], 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_mut_panics_when_key_is_not_present() {
+        let mut chain: ChainMap<&str, i32> = ChainMap::new();
+
+        chain["notset"] = 1;
+    }
+
+    #[test]
+    fn equal_chains_hash_equally() {
+        use crate::bloom::hash_key;
+
+        let chain_with = |first: Vec<(&'static str, i32)>, second: Vec<(&'static str, i32)>| {
+            let mut chain: ChainMap<_, _> = ChainMap::new();
+            chain.push_map(first.into_iter().collect());
+            chain.push_map(second.into_iter().collect());
+            chain
+        };
+
+        let mut a = chain_with(vec![("a", 1), ("b", 2)], vec![("a", 3)]);
+        let b = chain_with(vec![("b", 2), ("a", 1)], vec![("a", 3)]);
+        assert_eq!(a, b);
+        assert_eq!(hash_key(&a), hash_key(&b));
+
+        let flattened = chain_with(vec![("a", 1), ("b", 2)], vec![]);
+        assert_ne!(hash_key(&a), hash_key(&flattened));
+
+        a.insert_tombstone("c");
+        a.remove_tombstone("c");
+        assert_eq!(a, b);
+        assert_eq!(hash_key(&a), hash_key(&b));
+
+        a.set_layer_enabled(1, false);
+        assert_ne!(hash_key(&a), hash_key(&b));
+    }
+
+    #[test]
+    fn from_maps_keeps_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let single = ChainMap::from(second_map.clone());
+        assert_eq!(single.layer_sizes(), vec![2]);
+        assert_eq!(single["first"], 2);
+
+        let chain = ChainMap::from(vec![first_map.clone(), second_map.clone()]);
+        let collected: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        assert_eq!(chain, collected);
+        assert_eq!(chain["first"], 1);
+    }
+
+    #[cfg(feature = "const-generics")]
+    #[test]
+    fn chain_from_array_keeps_precedence_order() {
+        let mut first = HashMap::new();
+        first.insert(1, "first");
+
+        let mut second = HashMap::new();
+        second.insert(1, "second");
+        second.insert(2, "second");
+
+        let chain = ChainMap::from([first, second, HashMap::new()]);
+        assert_eq!(chain.layer_sizes(), vec![1, 2, 0]);
+        assert_eq!(chain[&1], "first");
+        assert_eq!(chain[&2], "second");
+    }
+
+    #[cfg(feature = "const-generics")]
+    #[test]
+    fn chain_get_many_resolves_array_of_keys() {
+        let mut first = HashMap::new();
+        first.insert("first", 1);
+
+        let mut second = HashMap::new();
+        second.insert("first", 2);
+        second.insert("second", 2);
+
+        let chain: ChainMap<_, _> = vec![first, second].into_iter().collect();
+
+        assert_eq!(
+            chain.get_many(["second", "missing", "first"]),
+            [Some(&2), None, Some(&1)]
+        );
+        let none: [&str; 0] = [];
+        assert_eq!(chain.get_many(none), []);
+    }
+
+    #[test]
+    fn collecting_pairs_creates_single_map() {
+        let chain: ChainMap<_, _> = vec![("first", 1), ("second", 2), ("first", 3)]
+            .into_iter()
+            .collect();
+
+        assert_eq!(chain.layer_sizes(), vec![2]);
+        assert_eq!(chain["first"], 3);
+        assert_eq!(chain["second"], 2);
+
+        let empty: ChainMap<&str, i32> = iter::empty::<(&str, i32)>().collect();
+        assert_eq!(empty.layer_sizes(), vec![0]);
+    }
+
+    #[test]
+    fn extend_adds_to_end_of_chain() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 1);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let mut chain: ChainMap<_, _> =
+            vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.get("second"), Some(&2));
+        assert_eq!(chain.get("third"), Some(&3));
+        assert_eq!(chain.get("fourth"), None);
+
+        let mut fourth_map = HashMap::new();
+        fourth_map.insert("first", 4);
+        fourth_map.insert("second", 4);
+        fourth_map.insert("third", 4);
+        fourth_map.insert("fourth", 4);
+
+        chain.extend(vec![fourth_map]);
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.get("second"), Some(&2));
+        assert_eq!(chain.get("third"), Some(&3));
+        assert_eq!(chain.get("fourth"), Some(&4));
+    }
+
+    #[test]
+    fn iter_yields_each_key_once_with_precedence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        let mut entries: Vec<_> = chain.iter().collect();
+        entries.sort();
+
+        assert_eq!(
+            entries,
+            vec![(&"first", &1), (&"second", &2), (&"third", &3)]
+        );
+    }
+
+    #[test]
+    fn iter_skips_empty_maps() {
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let chain: ChainMap<_, _> = vec![HashMap::new(), second_map, HashMap::new()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(chain.iter().collect::<Vec<_>>(), vec![(&"second", &2)]);
+        assert_eq!(ChainMap::<&str, i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn keys_yields_each_key_once() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        let mut keys: Vec<_> = chain.keys().collect();
+        keys.sort();
+
+        assert_eq!(keys, vec![&"first", &"second", &"third"]);
+    }
+
+    #[test]
+    fn values_skips_shadowed_values() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        let mut values: Vec<_> = chain.values().collect();
+        values.sort();
+
+        assert_eq!(values, vec![&1, &2, &3]);
+    }
+
+    #[test]
+    fn iter_prefix_yields_matching_keys_once_with_precedence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("db.host", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("db.host", 2);
+        second_map.insert("db.port", 2);
+        second_map.insert("dbg", 2);
+        second_map.insert("net.db.host", 2);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        let mut entries: Vec<_> = chain.iter_prefix("db.").collect();
+        entries.sort();
+        assert_eq!(entries, vec![(&"db.host", &1), (&"db.port", &2)]);
+
+        let mut keys: Vec<_> = chain.keys_with_prefix("db").collect();
+        keys.sort();
+        assert_eq!(keys, vec![&"db.host", &"db.port", &"dbg"]);
+
+        assert_eq!(chain.keys_with_prefix("").count(), chain.len());
+        assert_eq!(chain.iter_prefix("missing.").next(), None);
+    }
+
+    #[test]
+    fn iter_all_includes_shadowed_entries_in_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("second", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, HashMap::new(), second_map, third_map]
+            .into_iter()
+            .collect();
+
+        let entries: Vec<_> = chain.iter_all().collect();
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0], (0, &"first", &1));
+        assert!(entries[1..3].contains(&(2, &"first", &2)));
+        assert!(entries[1..3].contains(&(2, &"second", &2)));
+        assert_eq!(entries[3], (3, &"second", &3));
+    }
+
+    #[test]
+    fn extend_with_pairs_writes_to_highest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        chain.extend(vec![("second", 10), ("third", 10)]);
+
+        assert_eq!(chain.layer_sizes(), vec![3, 1]);
+        assert_eq!(chain["second"], 10);
+        assert_eq!(chain.get_from_layer(1, "second"), Some(&2));
+
+        let mut empty: ChainMap<_, _> = ChainMap::new();
+        empty.extend(vec![("key", 1)]);
+        assert_eq!(empty.layer_sizes(), vec![1]);
+    }
+
+    #[test]
+    fn into_iter_yields_owned_resolved_entries() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        let mut entries: Vec<_> = chain.into_iter().collect();
+        entries.sort();
+
+        assert_eq!(entries, vec![("first", 1), ("second", 2), ("third", 3)]);
+    }
+
+    #[test]
+    fn iter_mut_only_modifies_resolved_values() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        for (_, value) in &mut chain {
+            *value += 10;
+        }
+
+        let mut entries: Vec<_> = (&chain).into_iter().collect();
+        entries.sort();
+
+        assert_eq!(entries, vec![(&"first", &11), (&"second", &12)]);
+
+        let shadowed: Vec<_> = chain.iter_all().filter(|entry| entry.0 == 1).collect();
+        assert!(shadowed.contains(&(1, &"first", &2)));
+    }
+
+    #[test]
+    fn iter_mut_yields_entries_alongside_own_tombstones() {
+        let mut second_map = HashMap::new();
+        second_map.insert("x", 2);
+        second_map.insert("y", 2);
+
+        let mut chain = ChainMap::new();
+        chain.push_map(HashMap::new());
+        chain.push_map(second_map);
+        chain.insert_tombstone("x");
+        chain.insert("x", 1);
+
+        let mut expected: Vec<_> = chain.iter().map(|(&k, &v)| (k, v)).collect();
+        expected.sort();
+        let mut entries: Vec<_> = chain.iter_mut().map(|(&k, &mut v)| (k, v)).collect();
+        entries.sort();
+
+        assert_eq!(expected, vec![("x", 1), ("y", 2)]);
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn len_counts_distinct_keys() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain.len(), 3);
+        assert!(!chain.is_empty());
+    }
+
+    #[test]
+    fn is_empty_when_all_maps_are_empty() {
+        let mut chain: ChainMap<&str, i32> = ChainMap::new();
+        assert!(chain.is_empty());
+        assert_eq!(chain.len(), 0);
+
+        chain.push_map(HashMap::new());
+        chain.push_map(HashMap::new());
+        assert!(chain.is_empty());
+        assert_eq!(chain.len(), 0);
+    }
+
+    #[test]
+    fn total_len_and_layer_sizes_include_shadowed_entries() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let chain: ChainMap<_, _> = vec![first_map, HashMap::new(), second_map]
+            .into_iter()
+            .collect();
+
+        assert_eq!(chain.total_len(), 3);
+        assert_eq!(chain.layer_sizes(), vec![1, 0, 2]);
+        assert_eq!(ChainMap::<&str, i32>::new().total_len(), 0);
+    }
+
+    #[test]
+    fn layers_visits_maps_in_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+
+        let chain: ChainMap<_, _> = vec![first_map.clone(), second_map.clone()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(chain.layers().len(), 2);
+        assert_eq!(
+            chain.layers().collect::<Vec<_>>(),
+            vec![&first_map, &second_map]
+        );
+        assert_eq!(chain.layers().next_back(), Some(&second_map));
+    }
+
+    #[test]
+    fn layers_mut_allows_updating_maps_in_place() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        for map in chain.layers_mut() {
+            map.insert("shared", 0);
+        }
+        chain.layers_mut().next_back().unwrap().insert("first", 20);
+
+        assert_eq!(chain.layer_sizes(), vec![2, 3]);
+        assert_eq!(chain.get("first"), Some(&1));
+    }
+
+    #[test]
+    fn get_map_mut_targets_single_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        chain.get_map_mut(0).unwrap().remove("key");
+        assert_eq!(chain.get("key"), Some(&2));
+
+        chain.get_map_mut(1).unwrap().insert("key", 3);
+        assert_eq!(chain.get("key"), Some(&3));
+
+        assert!(chain.get_map_mut(2).is_none());
+    }
+
+    #[test]
+    fn get_mut_modifies_highest_precedence_value() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        *chain.get_mut("first").unwrap() = 10;
+        *chain.get_mut("second").unwrap() = 20;
+
+        assert_eq!(chain.get("first"), Some(&10));
+        assert_eq!(chain.get("second"), Some(&20));
+        assert_eq!(chain.iter_all().filter(|entry| entry.0 == 1).count(), 2);
+        assert!(chain.iter_all().any(|entry| entry == (1, &"first", &2)));
+        assert_eq!(chain.get_mut("third"), None);
+    }
+
+    #[test]
+    fn get_key_value_mut_returns_stored_key_of_highest_precedence_entry() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        let (key, value) = chain.get_key_value_mut("first").unwrap();
+        assert_eq!(*key, "first");
+        *value = 10;
+
+        assert_eq!(chain.get("first"), Some(&10));
+        assert_eq!(chain.get_from_layer(1, "first"), Some(&2));
+        assert_eq!(chain.get_key_value_mut("second"), Some((&"second", &mut 2)));
+        assert_eq!(chain.get_key_value_mut("third"), None);
+
+        chain.set_layer_enabled(0, false);
+        assert_eq!(chain.get_key_value_mut("first"), Some((&"first", &mut 2)));
+    }
+
+    #[test]
+    fn get_many_slice_matches_get() {
+        let mut chain: ChainMap<_, _> = (0..4)
+            .map(|layer| {
+                (0..100)
+                    .filter(|key| key % 4 >= layer)
+                    .map(|key| (key, layer))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect();
+        chain.remove_all(&7);
+        chain.insert_tombstone(10);
+        chain.set_layer_enabled(2, false);
+        chain.enable_bloom_filters();
+
+        let keys: Vec<i32> = (-10..110).collect();
+        let key_refs: Vec<&i32> = keys.iter().collect();
+        let values = chain.get_many_slice(&key_refs);
+
+        assert_eq!(values.len(), keys.len());
+        for (key, value) in keys.iter().zip(values) {
+            assert_eq!(value, chain.get(key));
+        }
+        assert!(chain.get_many_slice::<i32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn get_or_default_and_get_or_else_clone_resolved_value() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", vec![1]);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", vec![2]);
+        second_map.insert("second", vec![2]);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        assert_eq!(chain.get_or_default("first"), vec![1]);
+        assert_eq!(chain.get_or_default("third"), Vec::<i32>::new());
+        assert_eq!(chain.get_or_else("second", || unreachable!()), vec![2]);
+        assert_eq!(chain.get_or_else("third", || vec![3]), vec![3]);
+    }
+
+    #[test]
+    fn insert_writes_to_highest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        assert_eq!(chain.insert("second", 20), None);
+        assert_eq!(chain.insert("first", 10), Some(1));
+
+        assert_eq!(chain.get("first"), Some(&10));
+        assert_eq!(chain.get("second"), Some(&20));
+        assert_eq!(chain.layer_sizes(), vec![2, 1]);
+    }
+
+    #[test]
+    fn insert_creates_map_when_chain_is_empty() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+
+        assert_eq!(chain.insert("first", 1), None);
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.layer_sizes(), vec![1]);
+    }
+
+    #[test]
+    fn entry_refers_to_highest_precedence_occurrence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        match chain.entry("first") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.insert(10), 1);
+            }
+            Entry::Vacant(_) => panic!("expected an occupied entry"),
+        }
+
+        match chain.entry("second") {
+            Entry::Occupied(entry) => {
+                assert_eq!(entry.remove(), 2);
+            }
+            Entry::Vacant(_) => panic!("expected an occupied entry"),
+        }
+
+        assert_eq!(chain.get("first"), Some(&10));
+        assert_eq!(chain.get("second"), None);
+        assert_eq!(chain.layer_sizes(), vec![1, 1]);
+    }
+
+    #[test]
+    fn vacant_entry_inserts_into_highest_precedence_map() {
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![HashMap::new(), second_map].into_iter().collect();
+
+        chain.entry("first").or_insert(1);
+        chain
+            .entry("second")
+            .and_modify(|value| *value += 20)
+            .or_insert(0);
+
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.get("second"), Some(&22));
+        assert_eq!(chain.layer_sizes(), vec![1, 1]);
+    }
+
+    #[test]
+    fn get_or_insert_with_only_inserts_missing_keys_into_top_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        *chain.get_or_insert_with("second", || unreachable!()) += 1;
+        assert_eq!(chain.get_from_layer(1, "second"), Some(&3));
+
+        assert_eq!(*chain.get_or_insert_with("third", || 3), 3);
+        assert_eq!(chain.source_of("third"), Some(0));
+        assert_eq!(chain.layer_sizes(), vec![2, 1]);
+
+        let mut empty: ChainMap<_, _> = ChainMap::new();
+        assert_eq!(*empty.get_or_insert_with("key", || 0), 0);
+        assert_eq!(empty.layer_sizes(), vec![1]);
+    }
+
+    #[test]
+    fn entry_creates_map_when_chain_is_empty() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+
+        *chain.entry("first").or_default() += 5;
+
+        assert_eq!(chain.get("first"), Some(&5));
+        assert_eq!(chain.layer_sizes(), vec![1]);
+    }
+
+    #[test]
+    fn remove_only_affects_highest_precedence_occurrence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        assert_eq!(chain.remove("second"), Some(2));
+        assert_eq!(chain.get("second"), None);
+
+        assert_eq!(chain.remove("first"), Some(1));
+        assert_eq!(chain.get("first"), Some(&2));
+
+        assert_eq!(chain.remove("first"), Some(2));
+        assert_eq!(chain.remove("first"), None);
+        assert!(chain.is_empty());
+    }
+
+    #[test]
+    fn remove_all_removes_from_every_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+
+        let mut chain: ChainMap<_, _> =
+            vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain.remove_all("first"), vec![1, 3]);
+        assert_eq!(chain.get("first"), None);
+        assert_eq!(chain.get("second"), Some(&2));
+        assert_eq!(chain.remove_all("first"), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn retain_checks_resolved_values_and_removes_from_every_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("second", 2);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 1);
+        second_map.insert("third", 3);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 4);
+        third_map.insert("fourth", 4);
+
+        let mut chain: ChainMap<_, _> =
+            vec![first_map, second_map, third_map].into_iter().collect();
+
+        let mut visited = Vec::new();
+        chain.retain(|key, value| {
+            visited.push(*key);
+            *value % 2 == 0
+        });
+        visited.sort();
+
+        assert_eq!(visited, vec!["first", "fourth", "second", "third"]);
+        assert_eq!(chain.get("first"), None);
+        assert_eq!(chain.get("second"), Some(&2));
+        assert_eq!(chain.get("third"), None);
+        assert_eq!(chain.get("fourth"), Some(&4));
+        assert_eq!(chain.total_len(), 3);
+    }
+
+    #[test]
+    fn clear_keeps_maps_in_chain() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        chain.clear();
+
+        assert!(chain.is_empty());
+        assert_eq!(chain.layer_sizes(), vec![0, 0]);
+    }
+
+    #[test]
+    fn clear_layer_only_clears_single_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        chain.clear_layer(1);
+        assert_eq!(chain.layer_sizes(), vec![1, 0]);
+
+        chain.clear_layer(0);
+        assert!(chain.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn clear_layer_panics_when_index_is_out_of_bounds() {
+        let mut chain: ChainMap<&str, i32> = ChainMap::new();
+
+        chain.clear_layer(0);
+    }
+
+    #[test]
+    fn pop_map_removes_lowest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+        second_map.insert("other", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map.clone(), second_map.clone()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(chain.pop_map(), Some(second_map));
+        assert_eq!(chain.get("other"), None);
+        assert_eq!(chain.pop_map(), Some(first_map));
+        assert_eq!(chain.pop_map(), None);
+    }
+
+    #[test]
+    fn remove_map_extracts_map_from_middle_of_chain() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("second", 3);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map.clone(), third_map]
+            .into_iter()
+            .collect();
+
+        assert_eq!(chain.remove_map(1), Some(second_map));
+        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.get("second"), Some(&3));
+        assert_eq!(chain.layer_sizes(), vec![1, 1]);
+        assert_eq!(chain.remove_map(2), None);
+    }
+
+    #[test]
+    fn insert_map_at_places_map_at_precedence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("key", 3);
+        third_map.insert("other", 3);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, third_map].into_iter().collect();
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+        second_map.insert("other", 2);
+        chain.insert_map_at(1, second_map);
+
+        assert_eq!(chain.get("key"), Some(&1));
+        assert_eq!(chain.get("other"), Some(&2));
+
+        let mut fourth_map = HashMap::new();
+        fourth_map.insert("last", 4);
+        chain.insert_map_at(3, fourth_map);
+
+        assert_eq!(chain.layer_sizes(), vec![1, 2, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_map_at_panics_when_index_is_out_of_bounds() {
+        let mut chain: ChainMap<&str, i32> = ChainMap::new();
+
+        chain.insert_map_at(1, HashMap::new());
+    }
+
+    #[test]
+    fn insert_map_at_len_is_lowest_precedence_under_last_wins() {
+        let mut chain: ChainMap<_, _> = ChainMap::with_precedence(Precedence::LastWins);
+        chain.push_map(vec![("key", 1)].into_iter().collect());
+        chain.push_map(vec![("key", 2)].into_iter().collect());
+
+        chain.insert_map_at(2, vec![("key", 3), ("other", 3)].into_iter().collect());
+
+        assert_eq!(chain.layer_sizes(), vec![1, 1, 2]);
+        assert_eq!(chain["key"], 2);
+        assert_eq!(chain.source_of("other"), Some(2));
+    }
+
+    #[test]
+    fn push_front_map_takes_highest_precedence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut chain = ChainMap::new();
+        chain.push_map(first_map);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+        second_map.insert("other", 2);
+
+        chain.push_front_map(second_map);
+
+        assert_eq!(chain.get("key"), Some(&2));
+        assert_eq!(chain.layer_sizes(), vec![2, 1]);
+    }
+
+    #[test]
+    fn reorder_methods_change_precedence() {
+        let maps: Vec<HashMap<_, _>> = (0..4)
+            .map(|index| {
+                let mut map = HashMap::new();
+                map.insert("key", index);
+                map
+            })
+            .collect();
+        let mut chain: ChainMap<_, _> = maps.into_iter().collect();
+
+        let order = |chain: &ChainMap<&str, i32>| -> Vec<i32> {
+            chain.layers().map(|map| map["key"]).collect()
+        };
+
+        chain.swap_maps(0, 2);
+        assert_eq!(order(&chain), vec![2, 1, 0, 3]);
+        assert_eq!(chain.get("key"), Some(&2));
+
+        chain.rotate_left(1);
+        assert_eq!(order(&chain), vec![1, 0, 3, 2]);
+
+        chain.rotate_right(2);
+        assert_eq!(order(&chain), vec![3, 2, 1, 0]);
+
+        chain.reverse();
+        assert_eq!(order(&chain), vec![0, 1, 2, 3]);
+        assert_eq!(chain.get("key"), Some(&0));
+    }
+
+    #[test]
+    fn promote_map_preserves_order_of_other_maps() {
+        let maps: Vec<HashMap<_, _>> = (0..4)
+            .map(|index| {
+                let mut map = HashMap::new();
+                map.insert("key", index);
+                map
+            })
+            .collect();
+        let mut chain: ChainMap<_, _> = maps.into_iter().collect();
+
+        chain.promote_map(2);
+
+        let order: Vec<_> = chain.layers().map(|map| map["key"]).collect();
+        assert_eq!(order, vec![2, 0, 1, 3]);
+        assert_eq!(chain.get("key"), Some(&2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn promote_map_panics_when_index_is_out_of_bounds() {
+        let mut chain: ChainMap<&str, i32> = ChainMap::new();
+        chain.push_map(HashMap::new());
+
+        chain.promote_map(1);
+    }
+
+    #[test]
+    fn truncate_drops_lowest_precedence_maps() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        chain.truncate(5);
+        assert_eq!(chain.layer_sizes(), vec![1, 2]);
+
+        chain.truncate(1);
+        assert_eq!(chain.layer_sizes(), vec![1]);
+        assert_eq!(chain.get("second"), None);
+    }
+
+    #[test]
+    fn split_off_returns_lowest_precedence_maps() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("key", 3);
+        third_map.insert("other", 3);
+
+        let mut chain: ChainMap<_, _> =
+            vec![first_map, second_map, third_map].into_iter().collect();
+
+        let tail = chain.split_off(1);
+
+        assert_eq!(chain.layer_sizes(), vec![1]);
+        assert_eq!(tail.layer_sizes(), vec![1, 2]);
+        assert_eq!(tail.get("key"), Some(&2));
+        assert_eq!(tail.get("other"), Some(&3));
+    }
+
+    #[test]
+    fn append_moves_maps_to_lowest_precedence() {
+        let mut local = HashMap::new();
+        local.insert("key", 1);
+
+        let mut global = HashMap::new();
+        global.insert("key", 2);
+        global.insert("other", 2);
+
+        let mut chain: ChainMap<_, _> = ChainMap::with_precedence(Precedence::LastWins);
+        chain.push_map(local);
+
+        let mut other: ChainMap<_, _> = ChainMap::new();
+        other.push_named_map("global", global);
+        other.push_map(HashMap::new());
+        other.set_layer_enabled(1, false);
+
+        chain.append(other);
+        assert_eq!(chain.layer_sizes(), vec![1, 2, 0]);
+        assert_eq!(chain.layer_name(1), Some("global"));
+        assert_eq!(chain.layer_enabled(2), Some(false));
+        assert_eq!(chain["key"], 1);
+        assert_eq!(chain.source_of("other"), Some(1));
+
+        let tail = chain.split_off(1);
+        let view = chain.chained_with(&tail);
+        assert_eq!(view.get(&"key"), Some(&1));
+        assert_eq!(view.get(&"other"), Some(&2));
+        assert_eq!(view.source_of(&"other"), Some(1));
+        assert_eq!(view.layer_count(), 3);
+        assert_eq!(view.len(), 2);
+
+        chain.insert_tombstone("other");
+        let view = chain.chained_with(&tail);
+        assert!(!view.contains_key("other"));
+        assert_eq!(view.iter().collect::<Vec<_>>(), vec![(&"key", &1)]);
+    }
+
+    #[test]
+    fn new_child_adds_empty_highest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+
+        let parent: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        let mut child = parent.new_child();
+
+        assert_eq!(child.layer_sizes(), vec![0, 1, 1]);
+
+        child.insert("first", 10);
+
+        assert_eq!(child.get("first"), Some(&10));
+        assert_eq!(parent.get("first"), Some(&1));
+        assert_eq!(child.get("second"), Some(&2));
+    }
+
+    #[test]
+    fn parents_excludes_highest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("other", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+        let parents = chain.parents();
+
+        assert_eq!(parents.layer_sizes(), vec![1, 1]);
+        assert_eq!(parents.get("key"), Some(&2));
+        assert_eq!(parents.get("other"), Some(&3));
+        assert_eq!(chain.get("key"), Some(&1));
+
+        let empty: ChainMap<&str, i32> = ChainMap::new();
+        assert_eq!(empty.parents(), empty);
+    }
+
+    #[test]
+    fn flatten_collects_resolved_entries() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        let mut expected = HashMap::new();
+        expected.insert("first", 1);
+        expected.insert("second", 2);
+        expected.insert("third", 3);
+
+        assert_eq!(chain.flatten(), expected);
+        assert_eq!(chain.into_flattened(), expected);
+    }
+
+    #[test]
+    fn into_hash_map_keeps_resolved_values() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", vec![1]);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", vec![2]);
+        second_map.insert("second", vec![2]);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        let flat = chain.flatten();
+        let map: HashMap<_, _> = chain.into();
+
+        assert_eq!(map, flat);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["first"], vec![1]);
+    }
+
+    #[test]
+    fn squash_merges_range_preserving_precedence() {
+        let maps: Vec<HashMap<_, _>> = (0..5)
+            .map(|index| {
+                let mut map = HashMap::new();
+                map.insert("shared", index);
+                map.insert(["zero", "one", "two", "three", "four"][index], index);
+                map
+            })
+            .collect();
+        let mut chain: ChainMap<_, _> = maps.into_iter().collect();
+        let before = chain.clone().into_flattened();
+
+        chain.squash(1..4);
+
+        assert_eq!(chain.layer_sizes(), vec![2, 4, 2]);
+        let squashed = chain.layers().nth(1).unwrap();
+        assert_eq!(squashed["shared"], 1);
+        assert_eq!(squashed["three"], 3);
+        assert_eq!(chain.clone().into_flattened(), before);
+
+        chain.squash(..);
+        assert_eq!(chain.layer_sizes(), vec![6]);
+        assert_eq!(chain.into_flattened(), before);
+    }
+
+    #[test]
+    fn squash_with_empty_range_does_nothing() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, HashMap::new()].into_iter().collect();
+
+        chain.squash(1..1);
+        chain.squash(2..);
+
+        assert_eq!(chain.layer_sizes(), vec![1, 0]);
+    }
+
+    #[test]
+    fn named_maps_can_be_found_by_name() {
+        let mut cli = HashMap::new();
+        cli.insert("key", 1);
+
+        let mut env = HashMap::new();
+        env.insert("key", 2);
+
+        let mut chain = ChainMap::new();
+        chain.push_named_map("cli", cli.clone());
+        chain.push_map(HashMap::new());
+        chain.push_named_map(String::from("env"), env.clone());
+
+        assert_eq!(chain.layer_index_by_name("cli"), Some(0));
+        assert_eq!(chain.layer_index_by_name("env"), Some(2));
+        assert_eq!(chain.layer_index_by_name("file"), None);
+        assert_eq!(chain.layer_by_name("env"), Some(&env));
+        assert_eq!(chain.layer_name(0), Some("cli"));
+        assert_eq!(chain.layer_name(1), None);
+        assert_eq!(chain.layer_name(3), None);
+
+        chain.promote_map(2);
+        assert_eq!(chain.layer_index_by_name("env"), Some(0));
+        assert_eq!(chain.get("key"), Some(&2));
+    }
+
+    #[test]
+    fn duplicate_names_resolve_to_highest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut chain = ChainMap::new();
+        chain.push_named_map("plugin", first_map.clone());
+        chain.push_named_map("plugin", HashMap::new());
+
+        assert_eq!(chain.layer_by_name("plugin"), Some(&first_map));
+    }
+
+    #[test]
+    fn names_are_compared_by_eq() {
+        let mut named: ChainMap<&str, i32> = ChainMap::new();
+        named.push_named_map("cli", HashMap::new());
+
+        let mut unnamed: ChainMap<&str, i32> = ChainMap::new();
+        unnamed.push_map(HashMap::new());
+
+        assert_ne!(named, unnamed);
+    }
+
+    #[test]
+    fn precedence_is_compared_and_hashed() {
+        use crate::bloom::hash_key;
+
+        let mut first_wins: ChainMap<_, _> = ChainMap::new();
+        first_wins.push_map(vec![("key", 1)].into_iter().collect());
+
+        let mut last_wins: ChainMap<_, _> = ChainMap::with_precedence(Precedence::LastWins);
+        last_wins.push_map(vec![("key", 1)].into_iter().collect());
+
+        assert_ne!(first_wins, last_wins);
+        assert_ne!(hash_key(&first_wins), hash_key(&last_wins));
+        assert!(first_wins.eq_resolved(&last_wins));
+    }
+
+    #[test]
+    fn source_of_finds_highest_precedence_map() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("second", 3);
+        third_map.insert("third", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain.source_of("first"), Some(0));
+        assert_eq!(chain.source_of("second"), Some(1));
+        assert_eq!(chain.source_of("third"), Some(2));
+        assert_eq!(chain.source_of("fourth"), None);
     }
-}
 
-impl<K, V, S> PartialEq for ChainMap<K, V, S>
-where
-    K: Eq + Hash,
-    V: PartialEq,
-    S: BuildHasher,
-{
-    fn eq(&self, other: &ChainMap<K, V, S>) -> bool {
-        self.inner.eq(&other.inner)
+    #[test]
+    fn provenance_reports_name_of_source_map() {
+        let mut cli = HashMap::new();
+        cli.insert("first", 1);
+
+        let mut unnamed = HashMap::new();
+        unnamed.insert("first", 2);
+        unnamed.insert("second", 2);
+
+        let mut env = HashMap::new();
+        env.insert("third", 3);
+
+        let mut chain = ChainMap::new();
+        chain.push_named_map("cli", cli);
+        chain.push_map(unnamed);
+        chain.push_named_map("env", env);
+
+        assert_eq!(chain.source_name_of("first"), Some("cli"));
+        assert_eq!(chain.source_name_of("second"), None);
+        assert_eq!(chain.source_name_of("third"), Some("env"));
+        assert_eq!(chain.source_name_of("fourth"), None);
+
+        let (value, source) = chain.get_with_source("second").unwrap();
+        assert_eq!(*value, 2);
+        assert_eq!(source.index(), 1);
+        assert_eq!(source.name(), None);
+        assert_eq!(format!("{}", source), "layer 1");
+
+        let (value, source) = chain.get_with_source("third").unwrap();
+        assert_eq!(*value, 3);
+        assert_eq!(format!("{}", source), "env");
+
+        assert!(chain.get_with_source("fourth").is_none());
     }
-}
 
-impl<K, V, S> Eq for ChainMap<K, V, S>
-where
-    K: Eq + Hash,
-    V: Eq,
-    S: BuildHasher,
-{
-}
+    #[test]
+    fn get_all_yields_values_in_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let mut second_map = HashMap::new();
+        second_map.insert("other", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("key", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        assert_eq!(chain.get_all("key").collect::<Vec<_>>(), vec![&1, &3]);
+        assert_eq!(chain.get_all("other").collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(chain.get_all("missing").next(), None);
+    }
 
     #[test]
-    fn push_map_adds_to_chain() {
+    fn contains_key_in_layer_checks_single_map() {
         let mut first_map = HashMap::new();
         first_map.insert("first", 1);
 
-        let mut chain = ChainMap::new();
-        chain.push_map(first_map);
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
 
-        assert_eq!(chain.get("first"), Some(&1));
-        assert_eq!(chain.get("second"), None);
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        assert!(chain.contains_key_in_layer(0, "first"));
+        assert!(!chain.contains_key_in_layer(0, "second"));
+        assert!(chain.contains_key_in_layer(1, "first"));
+        assert!(chain.contains_key_in_layer(1, "second"));
+        assert!(!chain.contains_key_in_layer(2, "first"));
+    }
+
+    #[test]
+    fn get_from_layer_ignores_precedence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
 
         let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
         second_map.insert("second", 2);
 
-        chain.push_map(second_map);
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
 
-        assert_eq!(chain.get("second"), Some(&2));
+        assert_eq!(chain.get_from_layer(0, "first"), Some(&1));
+        assert_eq!(chain.get_from_layer(1, "first"), Some(&2));
+        assert_eq!(chain.get_from_layer(0, "second"), None);
+        assert_eq!(chain.get_from_layer(1, "second"), Some(&2));
+        assert_eq!(chain.get_from_layer(2, "first"), None);
     }
 
     #[test]
-    fn contains_key_searches_all_maps() {
+    fn shadowed_entries_reports_every_shadowed_occurrence() {
         let mut first_map = HashMap::new();
         first_map.insert("first", 1);
 
         let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
         second_map.insert("second", 2);
 
         let mut third_map = HashMap::new();
+        third_map.insert("first", 3);
+        third_map.insert("second", 3);
         third_map.insert("third", 3);
 
         let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
-        assert!(chain.contains_key("first"));
-        assert!(chain.contains_key("second"));
-        assert!(chain.contains_key("third"));
-        assert!(!chain.contains_key("fourth"));
+
+        let mut shadowed: Vec<_> = chain.shadowed_entries().collect();
+        shadowed.sort();
+
+        assert_eq!(
+            shadowed,
+            vec![(&"first", 0, 1), (&"first", 0, 2), (&"second", 1, 2)]
+        );
     }
 
     #[test]
-    fn get_follows_precedence_order() {
+    fn shadowed_keys_yields_each_key_once() {
         let mut first_map = HashMap::new();
         first_map.insert("first", 1);
 
         let mut second_map = HashMap::new();
-        second_map.insert("first", 1);
+        second_map.insert("first", 2);
         second_map.insert("second", 2);
 
         let mut third_map = HashMap::new();
@@ -319,19 +6793,19 @@ mod tests {
 
         let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
 
-        assert_eq!(chain.get("first"), Some(&1));
-        assert_eq!(chain.get("second"), Some(&2));
-        assert_eq!(chain.get("third"), Some(&3));
-        assert_eq!(chain.get("fourth"), None);
+        let mut shadowed: Vec<_> = chain.shadowed_keys().collect();
+        shadowed.sort();
+
+        assert_eq!(shadowed, vec![&"first", &"second"]);
     }
 
     #[test]
-    fn index_follows_precedence_order() {
+    fn stats_counts_shadowed_entries() {
         let mut first_map = HashMap::new();
         first_map.insert("first", 1);
 
         let mut second_map = HashMap::new();
-        second_map.insert("first", 1);
+        second_map.insert("first", 2);
         second_map.insert("second", 2);
 
         let mut third_map = HashMap::new();
@@ -340,53 +6814,522 @@ mod tests {
         third_map.insert("third", 3);
 
         let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+        let stats = chain.stats();
 
-        assert_eq!(chain["first"], 1);
-        assert_eq!(chain["second"], 2);
-        assert_eq!(chain["third"], 3);
+        assert_eq!(stats.layer_sizes(), &[1, 2, 3]);
+        assert_eq!(stats.total_entries(), 6);
+        assert_eq!(stats.unique_keys(), 3);
+        assert_eq!(stats.shadowed_entries(), 3);
     }
 
     #[test]
-    #[should_panic]
-    fn index_panics_when_key_is_not_present() {
-        let chain: ChainMap<&str, i32> = ChainMap::new();
+    fn diff_compares_resolved_values() {
+        let mut top = HashMap::new();
+        top.insert("shadowed", 1);
 
-        let _ = chain["notset"];
+        let mut before_base = HashMap::new();
+        before_base.insert("shadowed", 10);
+        before_base.insert("changed", 1);
+        before_base.insert("removed", 1);
+
+        let mut after_base = HashMap::new();
+        after_base.insert("shadowed", 20);
+        after_base.insert("changed", 2);
+        after_base.insert("added", 2);
+
+        let before: ChainMap<_, _> = vec![top.clone(), before_base].into_iter().collect();
+        let after: ChainMap<_, _> = vec![top, after_base].into_iter().collect();
+
+        let mut changes: Vec<_> = before.diff(&after).collect();
+        changes.sort_by_key(|change| *change.key());
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Added {
+                    key: &"added",
+                    value: &2
+                },
+                Change::Changed {
+                    key: &"changed",
+                    old: &1,
+                    new: &2
+                },
+                Change::Removed {
+                    key: &"removed",
+                    value: &1
+                },
+            ]
+        );
+        assert_eq!(before.diff(&before).count(), 0);
     }
 
     #[test]
-    fn extend_adds_to_end_of_chain() {
+    fn eq_resolved_ignores_layering_and_shadowed_entries() {
         let mut first_map = HashMap::new();
         first_map.insert("first", 1);
 
         let mut second_map = HashMap::new();
-        second_map.insert("first", 1);
+        second_map.insert("first", 2);
         second_map.insert("second", 2);
 
+        let layered: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        let mut flat_map = HashMap::new();
+        flat_map.insert("first", 1);
+        flat_map.insert("second", 2);
+        let mut flat: ChainMap<_, _> = ChainMap::new();
+        flat.push_map(flat_map);
+
+        assert_ne!(layered, flat);
+        assert!(layered.eq_resolved(&flat));
+        assert!(flat.eq_resolved(&layered));
+
+        flat.insert("third", 3);
+        assert!(!layered.eq_resolved(&flat));
+        assert!(!flat.eq_resolved(&layered));
+
+        flat.remove("third");
+        flat.insert("first", 2);
+        assert!(!layered.eq_resolved(&flat));
+    }
+
+    #[test]
+    fn diff_layers_only_reports_shared_keys() {
+        let mut first_map = HashMap::new();
+        first_map.insert("same", 1);
+        first_map.insert("different", 2);
+        first_map.insert("first_only", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("same", 1);
+        second_map.insert("different", 1);
+        second_map.insert("second_only", 1);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+
+        let mut shared: Vec<_> = chain.diff_layers(0, 1).collect();
+        shared.sort();
+
+        assert_eq!(shared, vec![(&"different", &2, &1), (&"same", &1, &1)]);
+    }
+
+    #[test]
+    fn get_merged_folds_from_lowest_precedence() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+        second_map.insert("other", 2);
+
         let mut third_map = HashMap::new();
-        third_map.insert("first", 3);
-        third_map.insert("second", 3);
+        third_map.insert("key", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+
+        let merged = chain.get_merged("key", Vec::new(), |mut acc, value| {
+            acc.push(*value);
+            acc
+        });
+
+        assert_eq!(merged, vec![3, 2, 1]);
+        assert_eq!(chain.get_merged("missing", 0, |acc, value| acc + value), 0);
+    }
+
+    #[test]
+    fn last_wins_pushes_to_highest_precedence() {
+        let mut chain: ChainMap<_, _> = ChainMap::with_precedence(Precedence::LastWins);
+
+        chain.push_named_map(
+            "defaults",
+            vec![("key", 1), ("default", 1)].into_iter().collect(),
+        );
+        chain.push_map(vec![("key", 2)].into_iter().collect());
+        chain.extend(vec![vec![("key", 3)]
+            .into_iter()
+            .collect::<HashMap<_, _>>()]);
+
+        assert_eq!(chain["key"], 3);
+        assert_eq!(chain["default"], 1);
+        assert_eq!(chain.layer_index_by_name("defaults"), Some(2));
+        assert_eq!(chain.split_off(1).precedence(), Precedence::LastWins);
+    }
+
+    #[test]
+    fn push_map_with_priority_orders_by_priority() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+
+        chain.push_map_with_priority(vec![("key", 1)].into_iter().collect(), 10);
+        chain.push_map_with_priority(vec![("key", 2)].into_iter().collect(), 30);
+        chain.push_map_with_priority(vec![("key", 3)].into_iter().collect(), 20);
+        chain.push_map_with_priority(vec![("key", 4)].into_iter().collect(), 20);
+
+        let values: Vec<_> = chain.get_all("key").cloned().collect();
+        assert_eq!(values, vec![2, 3, 4, 1]);
+
+        let mut chain: ChainMap<_, _> = ChainMap::with_precedence(Precedence::LastWins);
+
+        chain.push_map_with_priority(vec![("key", 1)].into_iter().collect(), 20);
+        chain.push_map_with_priority(vec![("key", 2)].into_iter().collect(), 20);
+        chain.push_map_with_priority(vec![("key", 3)].into_iter().collect(), 10);
+
+        let values: Vec<_> = chain.get_all("key").cloned().collect();
+        assert_eq!(values, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn tombstones_hide_lower_entries() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("deleted", 2);
+        second_map.insert("second", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("deleted", 3);
         third_map.insert("third", 3);
 
         let mut chain: ChainMap<_, _> =
             vec![first_map, second_map, third_map].into_iter().collect();
+        chain.insert_tombstone("deleted");
 
-        assert_eq!(chain.get("first"), Some(&1));
-        assert_eq!(chain.get("second"), Some(&2));
-        assert_eq!(chain.get("third"), Some(&3));
-        assert_eq!(chain.get("fourth"), None);
+        assert!(!chain.contains_key("deleted"));
+        assert_eq!(chain.get("deleted"), None);
+        assert_eq!(chain.get_mut("deleted"), None);
+        assert_eq!(chain.source_of("deleted"), None);
+        assert_eq!(chain.get_all("deleted").count(), 0);
+        assert_eq!(chain.remove("deleted"), None);
+        assert_eq!(chain.len(), 3);
+        assert!(chain.iter().all(|(key, _)| *key != "deleted"));
+        assert!(chain.iter_mut().all(|(key, _)| *key != "deleted"));
+        assert!(chain.clone().into_iter().all(|(key, _)| key != "deleted"));
+        assert_eq!(chain.get_from_layer(1, "deleted"), Some(&2));
 
-        let mut fourth_map = HashMap::new();
-        fourth_map.insert("first", 4);
-        fourth_map.insert("second", 4);
-        fourth_map.insert("third", 4);
-        fourth_map.insert("fourth", 4);
+        chain.squash(..2);
+        assert!(!chain.contains_key("deleted"));
+        assert_eq!(chain["second"], 2);
 
-        chain.extend(vec![fourth_map]);
+        chain.insert("deleted", 0);
+        assert_eq!(chain["deleted"], 0);
 
-        assert_eq!(chain.get("first"), Some(&1));
+        assert_eq!(chain.remove("deleted"), Some(0));
+        assert!(!chain.contains_key("deleted"));
+
+        assert!(chain.remove_tombstone("deleted"));
+        assert_eq!(chain["deleted"], 3);
+    }
+
+    #[test]
+    fn disabled_layers_are_skipped() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("shared", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("second", 2);
+        second_map.insert("shared", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        chain.set_layer_enabled(0, false);
+
+        assert!(!chain.contains_key("first"));
+        assert_eq!(chain.get("shared"), Some(&2));
+        assert_eq!(chain.source_of("shared"), Some(1));
+        assert_eq!(chain.get_all("shared").collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.iter_mut().count(), 2);
+        assert_eq!(chain.clone().into_iter().count(), 2);
+        assert_eq!(chain.get_from_layer(0, "shared"), Some(&1));
+
+        chain.retain(|_, _| false);
+        assert!(chain.is_empty());
+        assert_eq!(chain.layer_sizes(), vec![2, 0]);
+
+        chain.set_layer_enabled(0, true);
+        assert_eq!(chain["shared"], 1);
+    }
+
+    #[test]
+    fn disabled_layers_do_not_mask() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(HashMap::new());
+        chain.push_map(vec![("key", 1)].into_iter().collect());
+
+        chain.insert_tombstone("key");
+        assert!(!chain.contains_key("key"));
+
+        chain.set_layer_enabled(0, false);
+        assert_eq!(chain["key"], 1);
+        assert_eq!(chain.iter().count(), 1);
+    }
+
+    #[test]
+    fn iter_continues_past_disabled_layers() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("first", 1)].into_iter().collect());
+        chain.push_map(vec![("second", 2)].into_iter().collect());
+        chain.push_map(vec![("third", 3)].into_iter().collect());
+
+        chain.set_layer_enabled(1, false);
+
+        let mut keys: Vec<_> = chain.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![&"first", &"third"]);
+    }
+
+    #[test]
+    fn writes_skip_disabled_layers() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(HashMap::new());
+        chain.push_map(vec![("key", 1)].into_iter().collect());
+        chain.set_layer_enabled(0, false);
+
+        assert_eq!(chain.insert("key", 2), Some(1));
+        *chain.entry("other").or_insert(0) += 1;
+        assert_eq!(*chain.get_or_insert_with("lazy", || 3), 3);
+        chain.insert_tombstone("gone");
+        assert!(chain.remove_tombstone("gone"));
+
+        assert_eq!(chain.layer_sizes(), vec![0, 3]);
+        assert_eq!(chain["key"], 2);
+
+        chain.set_layer_enabled(1, false);
+        chain.insert("key", 4);
+
+        assert_eq!(chain.layer_sizes(), vec![1, 0, 3]);
+        assert_eq!(chain.layer_enabled(0), Some(true));
+        assert_eq!(chain["key"], 4);
+    }
+
+    #[test]
+    fn squash_keeps_disabled_layers() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("first", 1)].into_iter().collect());
+        chain.push_map(vec![("first", 2), ("second", 2)].into_iter().collect());
+        chain.push_map(vec![("third", 3)].into_iter().collect());
+        chain.push_named_map("fourth", vec![("fourth", 4)].into_iter().collect());
+        chain.push_map(vec![("fifth", 5), ("first", 5)].into_iter().collect());
+        chain.insert_tombstone("third");
+        chain.set_layer_enabled(0, false);
+        chain.set_layer_enabled(3, false);
+
+        chain.squash(..);
+
+        assert_eq!(chain.layer_sizes(), vec![1, 3, 1, 2]);
+        assert_eq!(chain.layer_enabled(0), Some(false));
+        assert_eq!(chain.layer_enabled(2), Some(false));
+        assert_eq!(chain.layer_name(2), Some("fourth"));
+        assert_eq!(chain["first"], 2);
+        assert_eq!(chain["third"], 3);
+
+        chain.set_layer_enabled(0, true);
+        chain.set_layer_enabled(2, true);
+        assert_eq!(chain["first"], 1);
+        assert_eq!(chain["fourth"], 4);
+        assert_eq!(chain.get("third"), None);
+    }
+
+    #[test]
+    fn stats_skip_disabled_layers_and_count_tombstones() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("first", 1), ("second", 1)].into_iter().collect());
+        chain.push_map(vec![("first", 2), ("second", 2)].into_iter().collect());
+        chain.push_map(vec![("first", 3), ("third", 3)].into_iter().collect());
+        chain.set_layer_enabled(0, false);
+        chain.insert_tombstone("third");
+
+        let stats = chain.stats();
+        assert_eq!(stats.layer_sizes(), &[2, 2, 2]);
+        assert_eq!(stats.unique_keys(), chain.len());
+        assert_eq!(stats.unique_keys(), 2);
+        assert_eq!(stats.shadowed_entries(), 2);
+    }
+
+    #[test]
+    fn shadowed_entries_skip_disabled_layers_and_follow_tombstones() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("first", 1), ("second", 1)].into_iter().collect());
+        chain.push_map(vec![("first", 2)].into_iter().collect());
+        chain.push_map(vec![("first", 3), ("third", 3)].into_iter().collect());
+        chain.push_map(vec![("third", 4)].into_iter().collect());
+        chain.set_layer_enabled(0, false);
+        chain.insert_tombstone("third");
+
+        let mut shadowed: Vec<_> = chain.shadowed_entries().collect();
+        shadowed.sort();
+        assert_eq!(
+            shadowed,
+            vec![(&"first", 1, 2), (&"third", 1, 2), (&"third", 1, 3)]
+        );
+        assert_eq!(shadowed.len(), chain.stats().shadowed_entries());
+
+        let mut keys: Vec<_> = chain.shadowed_keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![&"first", &"third"]);
+    }
+
+    #[test]
+    fn push_scope_removes_map_on_drop() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.insert("x", 1);
+
+        {
+            let mut outer = chain.push_scope();
+            outer.insert("x", 2);
+            outer.insert("y", 2);
+
+            {
+                let mut inner = outer.push_scope();
+                inner.insert("x", 3);
+                assert_eq!(inner["x"], 3);
+                assert_eq!(inner["y"], 2);
+            }
+
+            assert_eq!(outer["x"], 2);
+            assert_eq!(outer.layer_sizes(), vec![2, 1]);
+        }
+
+        assert_eq!(chain["x"], 1);
+        assert!(!chain.contains_key("y"));
+        assert_eq!(chain.layer_sizes(), vec![1]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn push_scope_is_panic_safe() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.insert("x", 1);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut scope = chain.push_scope();
+            scope.insert("x", 2);
+            panic!("scope failed");
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(chain["x"], 1);
+        assert_eq!(chain.layer_sizes(), vec![1]);
+    }
+
+    #[test]
+    fn transaction_commit_merges_into_top_map() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("kept", 1), ("replaced", 1)].into_iter().collect());
+        chain.push_map(vec![("deleted", 2), ("readded", 2)].into_iter().collect());
+
+        let mut transaction = chain.begin_transaction();
+        transaction.insert("replaced", 10);
+        transaction.insert("added", 10);
+        transaction.remove("deleted");
+        transaction.remove("readded");
+        transaction.insert("readded", 10);
+
+        assert!(!transaction.contains_key("deleted"));
+        assert_eq!(transaction.layer_sizes().len(), 3);
+        transaction.commit();
+
+        assert_eq!(chain.layer_sizes().len(), 2);
+        assert_eq!(chain["kept"], 1);
+        assert_eq!(chain["replaced"], 10);
+        assert_eq!(chain["added"], 10);
+        assert_eq!(chain["readded"], 10);
+        assert!(!chain.contains_key("deleted"));
+        assert_eq!(chain.get_from_layer(1, "deleted"), Some(&2));
+    }
+
+    #[test]
+    fn dropped_transaction_rolls_back() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.insert("key", 1);
+        let expected = chain.clone();
+
+        {
+            let mut transaction = chain.begin_transaction();
+            transaction.insert("key", 2);
+            transaction.remove("key");
+        }
+
+        assert_eq!(chain, expected);
+    }
+
+    #[test]
+    fn snapshot_is_unaffected_by_later_changes() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        chain.push_map(vec![("key", 1), ("shadowed", 1)].into_iter().collect());
+        chain.push_map(vec![("shadowed", 2), ("lower", 2)].into_iter().collect());
+
+        let snapshot = chain.snapshot();
+        let shared = snapshot.clone();
+
+        chain.insert("key", 10);
+        chain.insert_tombstone("lower");
+
+        assert_eq!(snapshot.len(), 3);
+        assert_eq!(snapshot["key"], 1);
+        assert_eq!(snapshot["shadowed"], 1);
+        assert_eq!(snapshot["lower"], 2);
+        assert!(Arc::ptr_eq(&snapshot.inner, &shared.inner));
+    }
+
+    #[test]
+    fn bloom_filters_track_inserted_keys() {
+        let keys: Vec<String> = (0..100).map(|index| format!("{}", index)).collect();
+
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        chain.enable_bloom_filters();
+        assert!(chain.inner.iter().all(|link| link.bloom.is_some()));
+
+        for (index, key) in keys.iter().enumerate() {
+            chain.insert(key.as_str(), index);
+        }
+        *chain.entry("entry").or_insert(0) += 1;
+        assert_eq!(chain.get("42"), Some(&42));
+        assert_eq!(chain.get("entry"), Some(&1));
+        assert_eq!(chain.source_of("second"), Some(1));
+        assert!(!chain.contains_key("missing"));
+
+        chain.get_map_mut(1).unwrap().insert("direct", 2);
+        assert!(chain.inner[1].bloom.is_none());
+        assert_eq!(
+            chain.get_with_source("direct").map(|(value, _)| value),
+            Some(&2)
+        );
+
+        chain.squash(..);
+        assert!(chain.inner[0].bloom.is_some());
         assert_eq!(chain.get("second"), Some(&2));
-        assert_eq!(chain.get("third"), Some(&3));
-        assert_eq!(chain.get("fourth"), Some(&4));
+        assert_eq!(chain.get("direct"), Some(&2));
+
+        chain.disable_bloom_filters();
+        assert!(chain.inner[0].bloom.is_none());
+    }
+
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn short_chains_store_maps_inline() {
+        let mut chain: ChainMap<_, _> = ChainMap::new();
+        for i in 0..INLINE_LINKS {
+            chain.push_map(HashMap::new());
+            chain.insert(i, i);
+        }
+        assert!(!chain.inner.spilled());
+
+        let tail = chain.split_off(1);
+        assert_eq!(tail.layer_sizes(), vec![0; INLINE_LINKS - 1]);
+
+        chain.extend(vec![HashMap::new(); INLINE_LINKS]);
+        assert!(chain.inner.spilled());
+        assert_eq!(chain.len(), INLINE_LINKS);
     }
 }
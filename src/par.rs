@@ -0,0 +1,371 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+use rayon::iter::plumbing::UnindexedConsumer;
+use rayon::prelude::*;
+
+use super::ChainMap;
+
+impl<K, V, S> ChainMap<K, V, S>
+where
+    K: Hash + Eq + Sync,
+    V: Sync,
+    S: BuildHasher + Sync,
+{
+    /// Looks up a batch of keys in parallel, returning the
+    /// highest-precedence value for each key in the same order as the keys.
+    ///
+    /// The keys are split across the threads of the current [`rayon`] thread
+    /// pool, with each key looked up as in [`get`]. This method is only
+    /// available with the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(
+    ///     chain.par_get_many(&["first", "second", "third"]),
+    ///     vec![Some(&1), Some(&2), None]
+    /// );
+    /// ```
+    ///
+    /// [`rayon`]: https://docs.rs/rayon
+    /// [`get`]: struct.ChainMap.html#method.get
+    pub fn par_get_many<Q>(&self, keys: &[Q]) -> Vec<Option<&V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + Sync,
+    {
+        keys.par_iter().map(|key| self.get(key)).collect()
+    }
+
+    /// A parallel iterator visiting all key-value pairs in the unified view
+    /// of the chain, in arbitrary order.
+    ///
+    /// Each key is yielded once, with its highest-precedence value, exactly
+    /// as in [`iter`]. This method is only available with the `rayon`
+    /// feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// let mut entries: Vec<_> = chain.par_iter().collect();
+    /// entries.sort();
+    /// assert_eq!(entries, vec![(&"first", &1), (&"second", &2)]);
+    /// ```
+    ///
+    /// [`iter`]: struct.ChainMap.html#method.iter
+    pub fn par_iter(&self) -> ParIter<'_, K, V, S> {
+        ParIter { chain: self }
+    }
+
+    /// A parallel iterator visiting all distinct keys in the chain, in
+    /// arbitrary order.
+    ///
+    /// This method is only available with the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.par_keys().count(), 2);
+    /// ```
+    pub fn par_keys(&self) -> ParKeys<'_, K, V, S> {
+        ParKeys {
+            inner: self.par_iter(),
+        }
+    }
+
+    /// A parallel iterator visiting the highest-precedence value for each key
+    /// in the chain, in arbitrary order.
+    ///
+    /// This method is only available with the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// assert_eq!(chain.par_values().sum::<i32>(), 3);
+    /// ```
+    pub fn par_values(&self) -> ParValues<'_, K, V, S> {
+        ParValues {
+            inner: self.par_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoParallelIterator for &'a ChainMap<K, V, S>
+where
+    K: Hash + Eq + Sync,
+    V: Sync,
+    S: BuildHasher + Sync,
+{
+    type Item = (&'a K, &'a V);
+    type Iter = ParIter<'a, K, V, S>;
+
+    fn into_par_iter(self) -> ParIter<'a, K, V, S> {
+        self.par_iter()
+    }
+}
+
+impl<K, V, S> FromParallelIterator<HashMap<K, V, S>> for ChainMap<K, V, S>
+where
+    K: Send,
+    V: Send,
+    S: Send,
+{
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = HashMap<K, V, S>>,
+    {
+        // Collecting into a `Vec` first keeps the maps in the order of the
+        // parallel iterator, so the precedence matches the sequential version
+        let maps: Vec<_> = par_iter.into_par_iter().collect();
+        maps.into_iter().collect()
+    }
+}
+
+/// A parallel iterator over the entries of a `ChainMap`.
+///
+/// This `struct` is created by the [`par_iter`] method on [`ChainMap`]. See
+/// its documentation for more.
+///
+/// [`par_iter`]: struct.ChainMap.html#method.par_iter
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ParIter<'a, K, V, S> {
+    chain: &'a ChainMap<K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for ParIter<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ParIter { chain: self.chain }
+    }
+}
+
+impl<'a, K, V, S> ParallelIterator for ParIter<'a, K, V, S>
+where
+    K: Hash + Eq + Sync,
+    V: Sync,
+    S: BuildHasher + Sync,
+{
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let links = &self.chain.inner;
+        links
+            .par_iter()
+            .enumerate()
+            .filter(|(_, link)| link.enabled)
+            .flat_map(move |(index, link)| {
+                // An entry is only visible if no higher-precedence map
+                // contains or deletes its key
+                link.map
+                    .par_iter()
+                    .filter(move |&(key, _)| !links[..index].iter().any(|link| link.hides(key)))
+            })
+            .drive_unindexed(consumer)
+    }
+}
+
+/// A parallel iterator over the distinct keys of a `ChainMap`.
+///
+/// This `struct` is created by the [`par_keys`] method on [`ChainMap`]. See
+/// its documentation for more.
+///
+/// [`par_keys`]: struct.ChainMap.html#method.par_keys
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ParKeys<'a, K, V, S> {
+    inner: ParIter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for ParKeys<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ParKeys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> ParallelIterator for ParKeys<'a, K, V, S>
+where
+    K: Hash + Eq + Sync,
+    V: Sync,
+    S: BuildHasher + Sync,
+{
+    type Item = &'a K;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.inner.map(|(key, _)| key).drive_unindexed(consumer)
+    }
+}
+
+/// A parallel iterator over the resolved values of a `ChainMap`.
+///
+/// This `struct` is created by the [`par_values`] method on [`ChainMap`].
+/// See its documentation for more.
+///
+/// [`par_values`]: struct.ChainMap.html#method.par_values
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ParValues<'a, K, V, S> {
+    inner: ParIter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for ParValues<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        ParValues {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S> ParallelIterator for ParValues<'a, K, V, S>
+where
+    K: Hash + Eq + Sync,
+    V: Sync,
+    S: BuildHasher + Sync,
+{
+    type Item = &'a V;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.inner.map(|(_, value)| value).drive_unindexed(consumer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn par_get_many_matches_get() {
+        let chain: ChainMap<_, _> = (0..8)
+            .map(|layer| {
+                (0..1000)
+                    .filter(|key| key % 8 >= layer)
+                    .map(|key| (key, layer))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect();
+        let keys: Vec<i32> = (-100..1100).collect();
+
+        let values = chain.par_get_many(&keys);
+
+        assert_eq!(values.len(), keys.len());
+        for (key, value) in keys.iter().zip(values) {
+            assert_eq!(value, chain.get(key));
+        }
+    }
+
+    #[test]
+    fn par_iter_matches_iter() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("shared", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("shared", 2);
+        second_map.insert("deleted", 2);
+        second_map.insert("hidden", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("hidden", 3);
+        third_map.insert("third", 3);
+
+        let mut chain: ChainMap<_, _> =
+            vec![first_map, second_map, third_map].into_iter().collect();
+        chain.insert_tombstone("deleted");
+        chain.set_layer_enabled(1, false);
+
+        let mut expected: Vec<_> = chain.iter().collect();
+        expected.sort();
+
+        let mut entries: Vec<_> = chain.par_iter().collect();
+        entries.sort();
+        assert_eq!(entries, expected);
+
+        let mut keys: Vec<_> = chain.par_keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![&"first", &"hidden", &"shared", &"third"]);
+
+        let total: i32 = (&chain).into_par_iter().map(|(_, value)| value).sum();
+        assert_eq!(total, chain.par_values().sum::<i32>());
+        assert_eq!(total, 8);
+    }
+
+    #[test]
+    fn collect_from_parallel_iterator_preserves_order() {
+        let chain: ChainMap<_, _> = (0..64)
+            .into_par_iter()
+            .map(|index| {
+                let mut map = HashMap::new();
+                map.insert("shared", index);
+                map.insert("own", index);
+                map
+            })
+            .collect();
+
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.get("shared"), Some(&0));
+        assert_eq!(
+            chain.get_all("own").cloned().collect::<Vec<_>>(),
+            (0..64).collect::<Vec<_>>()
+        );
+    }
+}
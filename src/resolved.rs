@@ -0,0 +1,61 @@
+//! Serializes a `ChainMap` as a single flat map of its resolved entries.
+//!
+//! The functions in this module can be used with serde's field attributes,
+//! such as `#[serde(with = "chain_map::resolved")]`, so a `ChainMap` field is
+//! serialized as the unified view that lookups see, rather than as the maps
+//! in the chain. Deserializing reads a flat map into a chain with a single
+//! map.
+//!
+//! This module is only available with the `serde` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use chain_map::ChainMap;
+//! use serde::{Serialize, Serializer};
+//!
+//! struct Settings {
+//!     values: ChainMap<String, String>,
+//! }
+//!
+//! impl Serialize for Settings {
+//!     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+//!         chain_map::resolved::serialize(&self.values, serializer)
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use super::ChainMap;
+
+/// Serializes the resolved entries of the chain as a single map.
+///
+/// See [`ChainMap::serialize_resolved`] for more.
+///
+/// [`ChainMap::serialize_resolved`]: ../struct.ChainMap.html#method.serialize_resolved
+pub fn serialize<K, V, S, T>(chain: &ChainMap<K, V, S>, serializer: T) -> Result<T::Ok, T::Error>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize,
+    S: BuildHasher,
+    T: Serializer,
+{
+    chain.serialize_resolved().serialize(serializer)
+}
+
+/// Deserializes a single map into a chain containing only that map.
+pub fn deserialize<'de, K, V, S, D>(deserializer: D) -> Result<ChainMap<K, V, S>, D::Error>
+where
+    K: Deserialize<'de> + Hash + Eq,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+    D: Deserializer<'de>,
+{
+    let map = HashMap::<K, V, S>::deserialize(deserializer)?;
+    Ok(vec![map].into_iter().collect())
+}
@@ -0,0 +1,175 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+use crate::compat::Cow;
+
+use super::{ChainMap, GetAll};
+
+/// A `ChainMap` that combines the values for a key from every map using a
+/// resolver closure, rather than always taking the highest-precedence value.
+///
+/// This `struct` is created by the [`with_resolver`] method on [`ChainMap`].
+/// The resolver is called with the key being looked up and an iterator over
+/// every value for that key, from the highest-precedence map to the lowest.
+/// It is only called for keys that are present in at least one map, so the
+/// iterator will always yield at least one value.
+///
+/// The key is passed to the resolver in the borrowed form `Q` used for
+/// lookups, which defaults to the key type itself. As with [`ChainMap::get`],
+/// `K` must implement `Borrow<Q>`, so a chain with `String` keys can be
+/// resolved with a resolver that takes `&str`.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use std::collections::HashMap;
+/// use chain_map::ChainMap;
+///
+/// let mut user = HashMap::new();
+/// user.insert("plugins", vec!["fmt"]);
+/// user.insert("theme", vec!["dark"]);
+///
+/// let mut system = HashMap::new();
+/// system.insert("plugins", vec!["lint"]);
+/// system.insert("theme", vec!["light"]);
+///
+/// let chain: ChainMap<_, _> = vec![user, system].into_iter().collect();
+/// let resolved = chain.with_resolver(|key: &str, mut values| {
+///     if key == "plugins" {
+///         Cow::Owned(values.flat_map(|list| list.iter().cloned()).collect())
+///     } else {
+///         Cow::Borrowed(values.next().unwrap())
+///     }
+/// });
+///
+/// assert_eq!(resolved.get("plugins").unwrap().as_ref(), &["fmt", "lint"]);
+/// assert_eq!(resolved.get("theme").unwrap().as_ref(), &["dark"]);
+/// assert_eq!(resolved.get("missing"), None);
+/// ```
+///
+/// [`with_resolver`]: struct.ChainMap.html#method.with_resolver
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`ChainMap::get`]: struct.ChainMap.html#method.get
+pub struct ResolvingChainMap<K, V, S, F, Q: ?Sized = K> {
+    chain: ChainMap<K, V, S>,
+    resolver: F,
+    marker: PhantomData<fn(&Q)>,
+}
+
+impl<K, V, S, F, Q: ?Sized> ResolvingChainMap<K, V, S, F, Q> {
+    /// Creates a new `ResolvingChainMap` from a chain and a resolver.
+    pub fn new(chain: ChainMap<K, V, S>, resolver: F) -> Self {
+        ResolvingChainMap {
+            chain,
+            resolver,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying chain.
+    pub fn chain(&self) -> &ChainMap<K, V, S> {
+        &self.chain
+    }
+
+    /// Returns a mutable reference to the underlying chain.
+    pub fn chain_mut(&mut self) -> &mut ChainMap<K, V, S> {
+        &mut self.chain
+    }
+
+    /// Consumes the `ResolvingChainMap`, returning the underlying chain.
+    pub fn into_chain(self) -> ChainMap<K, V, S> {
+        self.chain
+    }
+}
+
+impl<K, V, S, F, Q> ResolvingChainMap<K, V, S, F, Q>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    V: Clone,
+    S: BuildHasher,
+    F: for<'a> Fn(&'a Q, GetAll<'a, K, V, S, Q>) -> Cow<'a, V>,
+{
+    /// Returns the value for the given key, as combined by the resolver.
+    ///
+    /// Returns `None` without calling the resolver if no map contains the
+    /// key.
+    pub fn get<'a>(&'a self, k: &'a Q) -> Option<Cow<'a, V>> {
+        if self.chain.contains_key(k) {
+            Some((self.resolver)(k, self.chain.get_all(k)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<K, V, S, F, Q: ?Sized> Debug for ResolvingChainMap<K, V, S, F, Q>
+where
+    K: Hash + Eq + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResolvingChainMap")
+            .field("chain", &self.chain)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::{HashMap, String};
+
+    #[test]
+    fn resolver_sees_values_in_precedence_order() {
+        let mut first_map = HashMap::new();
+        first_map.insert("key", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("key", 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("key", 3);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map, third_map].into_iter().collect();
+        let resolved = chain
+            .with_resolver(|_, values| Cow::Owned(values.fold(0, |acc, value| acc * 10 + value)));
+
+        assert_eq!(resolved.get(&"key"), Some(Cow::Owned(123)));
+    }
+
+    #[test]
+    fn resolver_takes_borrowed_keys() {
+        let mut first_map = HashMap::new();
+        first_map.insert(String::from("key"), 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert(String::from("key"), 2);
+
+        let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        let resolved = chain
+            .with_resolver(|key: &str, values| Cow::Owned(key.len() as i32 * values.sum::<i32>()));
+
+        assert_eq!(resolved.get("key"), Some(Cow::Owned(9)));
+        assert_eq!(resolved.get("missing"), None);
+    }
+
+    #[test]
+    fn chain_mut_changes_resolved_values() {
+        let chain: ChainMap<&str, i32> = ChainMap::new();
+        let mut resolved = chain.with_resolver(|_, values| Cow::Owned(values.sum()));
+
+        resolved.chain_mut().push_map(HashMap::new());
+        resolved.chain_mut().insert("key", 2);
+        resolved
+            .chain_mut()
+            .push_map(vec![("key", 3)].into_iter().collect());
+
+        assert_eq!(resolved.get(&"key"), Some(Cow::Owned(5)));
+        assert_eq!(resolved.into_chain().get("key"), Some(&2));
+    }
+}
@@ -0,0 +1,829 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, IgnoredAny, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use super::{ChainMap, Link, Precedence};
+
+impl<K, V, S> ChainMap<K, V, S>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize,
+    S: BuildHasher,
+{
+    /// Returns an adapter that serializes the resolved entries of the chain
+    /// as a single map, rather than as the maps in the chain.
+    ///
+    /// Each key is serialized once, with its highest-precedence value, so
+    /// consumers see the same view as [`get`]. To use this for a field of a
+    /// type that derives `Serialize`, see the [`resolved`] module. This
+    /// method is only available with the `serde` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use chain_map::ChainMap;
+    /// use serde::Serialize;
+    ///
+    /// fn to_flat<T: Serialize>(value: &T) { /* ... */ }
+    ///
+    /// let mut first_map = HashMap::new();
+    /// first_map.insert("first", 1);
+    ///
+    /// let mut second_map = HashMap::new();
+    /// second_map.insert("first", 2);
+    /// second_map.insert("second", 2);
+    ///
+    /// let chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+    ///
+    /// // Serializes as a single map of `first` to 1 and `second` to 2
+    /// to_flat(&chain.serialize_resolved());
+    /// ```
+    ///
+    /// [`get`]: struct.ChainMap.html#method.get
+    /// [`resolved`]: resolved/index.html
+    pub fn serialize_resolved(&self) -> SerializeResolved<'_, K, V, S> {
+        SerializeResolved { chain: self }
+    }
+}
+
+/// Serializes the chain as a struct holding its precedence and the sequence
+/// of its maps, in precedence order, along with the name, priority,
+/// tombstones and enabled state of each map.
+impl<K, V, S> Serialize for ChainMap<K, V, S>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ChainMap", CHAIN_FIELDS.len())?;
+        state.serialize_field("precedence", &self.precedence)?;
+        state.serialize_field("layers", &SerializeLinks(&self.inner))?;
+        state.end()
+    }
+}
+
+/// Serializes the maps of a chain as a sequence.
+struct SerializeLinks<'a, K, V, S>(&'a [Link<K, V, S>]);
+
+impl<'a, K, V, S> Serialize for SerializeLinks<'a, K, V, S>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        serializer.collect_seq(self.0)
+    }
+}
+
+impl<K, V, S> Serialize for Link<K, V, S>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Layer", LAYER_FIELDS.len())?;
+        state.serialize_field("map", &self.map)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("priority", &self.priority)?;
+        state.serialize_field("tombstones", &self.tombstones)?;
+        state.serialize_field("enabled", &self.enabled)?;
+        state.end()
+    }
+}
+
+impl Serialize for Precedence {
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let index = match self {
+            Precedence::FirstWins => 0,
+            Precedence::LastWins => 1,
+        };
+        serializer.serialize_unit_variant("Precedence", index, PRECEDENCE_VARIANTS[index as usize])
+    }
+}
+
+/// An adapter that serializes the resolved entries of a `ChainMap` as a
+/// single map.
+///
+/// This `struct` is created by the [`serialize_resolved`] method on
+/// [`ChainMap`]. See its documentation for more.
+///
+/// [`serialize_resolved`]: struct.ChainMap.html#method.serialize_resolved
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct SerializeResolved<'a, K, V, S> {
+    chain: &'a ChainMap<K, V, S>,
+}
+
+impl<'a, K, V, S> Clone for SerializeResolved<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        SerializeResolved { chain: self.chain }
+    }
+}
+
+impl<'a, K, V, S> Serialize for SerializeResolved<'a, K, V, S>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize,
+    S: BuildHasher,
+{
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        serializer.collect_map(self.chain.iter())
+    }
+}
+
+/// Deserializes a chain in the form written by its `Serialize`
+/// implementation.
+///
+/// Only the `layers` field is required. A missing `precedence` defaults to
+/// `FirstWins`, and each map only needs its `map` field, with no name, a
+/// priority of 0, no tombstones and enabled as the defaults for the rest.
+impl<'de, K, V, S> Deserialize<'de> for ChainMap<K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("ChainMap", CHAIN_FIELDS, ChainMapVisitor(PhantomData))
+    }
+}
+
+const CHAIN_FIELDS: &[&str] = &["precedence", "layers"];
+const LAYER_FIELDS: &[&str] = &["map", "name", "priority", "tombstones", "enabled"];
+const PRECEDENCE_VARIANTS: &[&str] = &["FirstWins", "LastWins"];
+
+struct ChainMapVisitor<K, V, S>(PhantomData<ChainMap<K, V, S>>);
+
+impl<'de, K, V, S> Visitor<'de> for ChainMapVisitor<K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    type Value = ChainMap<K, V, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("struct ChainMap")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let precedence = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let layers: Vec<Link<K, V, S>> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(ChainMap {
+            inner: layers.into_iter().collect(),
+            precedence,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut precedence = None;
+        let mut layers: Option<Vec<Link<K, V, S>>> = None;
+        while let Some(field) = map.next_key_seed(FieldSeed(CHAIN_FIELDS))? {
+            match field {
+                Some(0) => precedence = Some(map.next_value()?),
+                Some(1) => layers = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let layers = layers.ok_or_else(|| de::Error::missing_field("layers"))?;
+        Ok(ChainMap {
+            inner: layers.into_iter().collect(),
+            precedence: precedence.unwrap_or_default(),
+        })
+    }
+}
+
+impl<'de, K, V, S> Deserialize<'de> for Link<K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Layer", LAYER_FIELDS, LinkVisitor(PhantomData))
+    }
+}
+
+struct LinkVisitor<K, V, S>(PhantomData<Link<K, V, S>>);
+
+impl<'de, K, V, S> Visitor<'de> for LinkVisitor<K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    type Value = Link<K, V, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("struct Layer")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let map: HashMap<K, V, S> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let mut link = Link::from(map);
+        link.name = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        link.priority = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        link.tombstones = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        link.enabled = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+        Ok(link)
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut map: Option<HashMap<K, V, S>> = None;
+        let mut name = None;
+        let mut priority = None;
+        let mut tombstones = None;
+        let mut enabled = None;
+        while let Some(field) = access.next_key_seed(FieldSeed(LAYER_FIELDS))? {
+            match field {
+                Some(0) => map = Some(access.next_value()?),
+                Some(1) => name = Some(access.next_value()?),
+                Some(2) => priority = Some(access.next_value()?),
+                Some(3) => tombstones = Some(access.next_value()?),
+                Some(4) => enabled = Some(access.next_value()?),
+                _ => {
+                    access.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let map = map.ok_or_else(|| de::Error::missing_field("map"))?;
+        let mut link = Link::from(map);
+        link.name = name.unwrap_or_default();
+        link.priority = priority.unwrap_or_default();
+        link.tombstones = tombstones.unwrap_or_default();
+        link.enabled = enabled.unwrap_or(true);
+        Ok(link)
+    }
+}
+
+impl<'de> Deserialize<'de> for Precedence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_enum("Precedence", PRECEDENCE_VARIANTS, PrecedenceVisitor)
+    }
+}
+
+struct PrecedenceVisitor;
+
+impl<'de> Visitor<'de> for PrecedenceVisitor {
+    type Value = Precedence;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("enum Precedence")
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (variant, access) = data.variant_seed(FieldSeed(PRECEDENCE_VARIANTS))?;
+        access.unit_variant()?;
+        match variant {
+            Some(0) => Ok(Precedence::FirstWins),
+            Some(1) => Ok(Precedence::LastWins),
+            _ => Err(de::Error::custom(
+                "unknown variant, expected `FirstWins` or `LastWins`",
+            )),
+        }
+    }
+}
+
+/// Deserializes a struct field or enum variant name as its index in a list
+/// of names, or `None` if it isn't in the list.
+struct FieldSeed(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = Option<usize>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed {
+    type Value = Option<usize>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a field identifier")
+    }
+
+    fn visit_u64<E>(self, index: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(index as usize).filter(|&index| index < self.0.len()))
+    }
+
+    fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.0.iter().position(|&field| field == name))
+    }
+
+    fn visit_bytes<E>(self, name: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.0.iter().position(|&field| field.as_bytes() == name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Precedence;
+    use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+    use serde::de::IntoDeserializer;
+    use serde::ser::{
+        Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple,
+        SerializeTupleStruct,
+    };
+    use std::collections::BTreeMap;
+
+    /// A minimal self-describing value, so the tests don't depend on a data
+    /// format crate.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub(crate) enum Value {
+        None,
+        Int(i64),
+        Str(String),
+        Seq(Vec<Value>),
+        Map(BTreeMap<Value, Value>),
+    }
+
+    pub(crate) fn to_value<T: Serialize + ?Sized>(value: &T) -> Value {
+        value.serialize(ValueSerializer).unwrap()
+    }
+
+    pub(crate) struct ValueSerializer;
+
+    pub(crate) struct SeqBuilder(Vec<Value>);
+
+    pub(crate) struct MapBuilder(BTreeMap<Value, Value>, Option<Value>);
+
+    fn unsupported<T>() -> Result<T, Error> {
+        Err(serde::ser::Error::custom("unsupported type"))
+    }
+
+    impl Serializer for ValueSerializer {
+        type Ok = Value;
+        type Error = Error;
+        type SerializeSeq = SeqBuilder;
+        type SerializeTuple = SeqBuilder;
+        type SerializeTupleStruct = SeqBuilder;
+        type SerializeTupleVariant = Impossible<Value, Error>;
+        type SerializeMap = MapBuilder;
+        type SerializeStruct = MapBuilder;
+        type SerializeStructVariant = Impossible<Value, Error>;
+
+        fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+            Ok(Value::Int(v as i64))
+        }
+        fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+            Ok(Value::Int(v.into()))
+        }
+        fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+            Ok(Value::Int(v.into()))
+        }
+        fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+            Ok(Value::Int(v.into()))
+        }
+        fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+            Ok(Value::Int(v))
+        }
+        fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+            Ok(Value::Int(v.into()))
+        }
+        fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+            Ok(Value::Int(v.into()))
+        }
+        fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+            Ok(Value::Int(v.into()))
+        }
+        fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+            Ok(Value::Int(v as i64))
+        }
+        fn serialize_f32(self, _: f32) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_f64(self, _: f64) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_char(self, v: char) -> Result<Value, Error> {
+            Ok(Value::Str(v.to_string()))
+        }
+        fn serialize_str(self, v: &str) -> Result<Value, Error> {
+            Ok(Value::Str(v.to_string()))
+        }
+        fn serialize_bytes(self, _: &[u8]) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_none(self) -> Result<Value, Error> {
+            Ok(Value::None)
+        }
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+            value.serialize(self)
+        }
+        fn serialize_unit(self) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_unit_struct(self, _: &'static str) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_unit_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            variant: &'static str,
+        ) -> Result<Value, Error> {
+            self.serialize_str(variant)
+        }
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            value: &T,
+        ) -> Result<Value, Error> {
+            value.serialize(self)
+        }
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, Error> {
+            Ok(SeqBuilder(Vec::with_capacity(len.unwrap_or(0))))
+        }
+        fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, Error> {
+            self.serialize_seq(Some(len))
+        }
+        fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SeqBuilder, Error> {
+            self.serialize_seq(Some(len))
+        }
+        fn serialize_tuple_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            unsupported()
+        }
+        fn serialize_map(self, _: Option<usize>) -> Result<MapBuilder, Error> {
+            Ok(MapBuilder(BTreeMap::new(), None))
+        }
+        fn serialize_struct(self, _: &'static str, _: usize) -> Result<MapBuilder, Error> {
+            self.serialize_map(None)
+        }
+        fn serialize_struct_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            unsupported()
+        }
+    }
+
+    impl SerializeSeq for SeqBuilder {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.0.push(value.serialize(ValueSerializer)?);
+            Ok(())
+        }
+        fn end(self) -> Result<Value, Error> {
+            Ok(Value::Seq(self.0))
+        }
+    }
+
+    impl SerializeTuple for SeqBuilder {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            SerializeSeq::serialize_element(self, value)
+        }
+        fn end(self) -> Result<Value, Error> {
+            SerializeSeq::end(self)
+        }
+    }
+
+    impl SerializeTupleStruct for SeqBuilder {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            SerializeSeq::serialize_element(self, value)
+        }
+        fn end(self) -> Result<Value, Error> {
+            SerializeSeq::end(self)
+        }
+    }
+
+    impl SerializeMap for MapBuilder {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+            self.1 = Some(key.serialize(ValueSerializer)?);
+            Ok(())
+        }
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            let key = self.1.take().expect("value serialized before key");
+            self.0.insert(key, value.serialize(ValueSerializer)?);
+            Ok(())
+        }
+        fn end(self) -> Result<Value, Error> {
+            Ok(Value::Map(self.0))
+        }
+    }
+
+    impl SerializeStruct for MapBuilder {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(
+            &mut self,
+            key: &'static str,
+            value: &T,
+        ) -> Result<(), Error> {
+            SerializeMap::serialize_entry(self, key, value)
+        }
+        fn end(self) -> Result<Value, Error> {
+            SerializeMap::end(self)
+        }
+    }
+
+    impl<'de> Deserializer<'de> for Value {
+        type Error = Error;
+
+        fn deserialize_any<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, Error> {
+            match self {
+                Value::None => visitor.visit_none(),
+                Value::Int(v) => visitor.visit_i64(v),
+                Value::Str(v) => visitor.visit_string(v),
+                Value::Seq(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+                Value::Map(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            }
+        }
+        fn deserialize_bool<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, Error> {
+            match self {
+                Value::Int(v) => visitor.visit_bool(v != 0),
+                other => other.deserialize_any(visitor),
+            }
+        }
+        fn deserialize_option<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, Error> {
+            match self {
+                Value::None => visitor.visit_none(),
+                other => visitor.visit_some(other),
+            }
+        }
+        fn deserialize_enum<W: Visitor<'de>>(
+            self,
+            _: &'static str,
+            _: &'static [&'static str],
+            visitor: W,
+        ) -> Result<W::Value, Error> {
+            match self {
+                Value::Str(variant) => visitor.visit_enum(variant.into_deserializer()),
+                other => other.deserialize_any(visitor),
+            }
+        }
+
+        serde::forward_to_deserialize_any! {
+            i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf unit unit_struct newtype_struct seq tuple tuple_struct map struct
+            identifier ignored_any
+        }
+    }
+
+    impl<'de> IntoDeserializer<'de, Error> for Value {
+        type Deserializer = Value;
+
+        fn into_deserializer(self) -> Value {
+            self
+        }
+    }
+
+    fn fields(entries: Vec<(&str, Value)>) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (Value::Str(key.to_string()), value))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn map(entries: &[(&str, i64)]) -> Value {
+        Value::Map(
+            entries
+                .iter()
+                .map(|&(key, value)| (Value::Str(key.to_string()), Value::Int(value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn serializes_precedence_and_layers() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("deleted", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain = ChainMap::new();
+        chain.push_named_map("cli", first_map);
+        chain.push_map_with_priority(second_map, -5);
+        chain.insert_tombstone("deleted");
+        chain.set_layer_enabled(1, false);
+
+        assert_eq!(
+            to_value(&chain),
+            fields(vec![
+                ("precedence", Value::Str("FirstWins".to_string())),
+                (
+                    "layers",
+                    Value::Seq(vec![
+                        fields(vec![
+                            ("map", map(&[("first", 1)])),
+                            ("name", Value::Str("cli".to_string())),
+                            ("priority", Value::Int(0)),
+                            (
+                                "tombstones",
+                                Value::Seq(vec![Value::Str("deleted".to_string())])
+                            ),
+                            ("enabled", Value::Int(1)),
+                        ]),
+                        fields(vec![
+                            ("map", map(&[("first", 2), ("second", 2)])),
+                            ("name", Value::None),
+                            ("priority", Value::Int(-5)),
+                            ("tombstones", Value::None),
+                            ("enabled", Value::Int(0)),
+                        ]),
+                    ])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn round_trips_layered_structure() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first".to_string(), 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first".to_string(), 2);
+        second_map.insert("deleted".to_string(), 2);
+
+        let mut third_map = HashMap::new();
+        third_map.insert("third".to_string(), 3);
+
+        let mut chain = ChainMap::with_precedence(Precedence::LastWins);
+        chain.push_map(third_map);
+        chain.push_map_with_priority(second_map, 1);
+        chain.push_named_map("override", first_map);
+        chain.insert_tombstone("deleted".to_string());
+        chain.set_layer_enabled(2, false);
+
+        let restored: ChainMap<String, i32> = ChainMap::deserialize(to_value(&chain)).unwrap();
+
+        assert_eq!(restored, chain);
+        assert_eq!(restored.precedence(), Precedence::LastWins);
+        assert_eq!(restored.layer_index_by_name("override"), Some(0));
+        assert_eq!(restored.layer_enabled(2), Some(false));
+        assert_eq!(restored.get("deleted"), None);
+        assert_eq!(restored.get("third"), None);
+        assert_eq!(restored["first"], 1);
+    }
+
+    #[test]
+    fn deserializes_missing_fields_as_defaults() {
+        let layers = Value::Seq(vec![
+            fields(vec![("map", map(&[("first", 1)]))]),
+            fields(vec![
+                ("map", map(&[("first", 2), ("second", 2)])),
+                ("enabled", Value::Int(0)),
+                ("unknown", Value::Int(0)),
+            ]),
+        ]);
+        let chain: ChainMap<String, i32> =
+            ChainMap::deserialize(fields(vec![("layers", layers)])).unwrap();
+
+        assert_eq!(chain.precedence(), Precedence::FirstWins);
+        assert_eq!(chain.layer_sizes(), vec![1, 2]);
+        assert_eq!(chain.layer_enabled(1), Some(false));
+        assert_eq!(chain["first"], 1);
+        assert_eq!(chain.get("second"), None);
+
+        let missing = ChainMap::<String, i32>::deserialize(fields(vec![])).unwrap_err();
+        assert_eq!(missing.to_string(), "missing field `layers`");
+    }
+
+    #[test]
+    fn serialize_resolved_flattens_chain() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+        first_map.insert("deleted", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let mut chain: ChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        chain.insert_tombstone("deleted");
+
+        let expected = map(&[("first", 1), ("second", 2)]);
+        assert_eq!(to_value(&chain.serialize_resolved()), expected);
+
+        struct Wrapper<'a>(&'a ChainMap<&'static str, i32>);
+        impl<'a> Serialize for Wrapper<'a> {
+            fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+                crate::resolved::serialize(self.0, serializer)
+            }
+        }
+        assert_eq!(to_value(&Wrapper(&chain)), expected);
+    }
+
+    #[test]
+    fn resolved_deserializes_into_single_map() {
+        let entries = vec![("first".to_string(), 1), ("second".to_string(), 2)];
+        let deserializer = MapDeserializer::<_, Error>::new(entries.into_iter());
+
+        let chain: ChainMap<String, i32> = crate::resolved::deserialize(deserializer).unwrap();
+
+        assert_eq!(chain.layer_sizes(), vec![2]);
+        assert_eq!(chain["first"], 1);
+        assert_eq!(chain["second"], 2);
+    }
+}
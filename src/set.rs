@@ -0,0 +1,234 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::iter::FromIterator;
+
+use crate::compat::{hash_set, HashSet, RandomState, Vec};
+
+/// A chain of [`HashSet`]s with a single view of their union.
+///
+/// This is the set counterpart to [`ChainMap`]. Sets are added in precedence
+/// order, so the first set added has the highest precedence, which matters
+/// when asking which set provides a value with [`source_of`].
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashSet;
+/// use chain_map::ChainSet;
+///
+/// let mut granted = HashSet::new();
+/// granted.insert("write");
+///
+/// let mut defaults = HashSet::new();
+/// defaults.insert("read");
+/// defaults.insert("write");
+///
+/// let mut chain = ChainSet::new();
+/// chain.push_set(granted);
+/// chain.push_set(defaults);
+///
+/// assert!(chain.contains("read"));
+/// assert!(!chain.contains("admin"));
+/// assert_eq!(chain.source_of("write"), Some(0));
+/// assert_eq!(chain.len(), 2);
+/// ```
+///
+/// [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`source_of`]: #method.source_of
+#[derive(Clone)]
+pub struct ChainSet<T, S = RandomState> {
+    inner: Vec<HashSet<T, S>>,
+}
+
+impl<T, S> ChainSet<T, S> {
+    /// Creates an empty `ChainSet`.
+    pub fn new() -> Self {
+        ChainSet { inner: Vec::new() }
+    }
+
+    /// Adds a set to the lowest-precedence end of the chain.
+    pub fn push_set(&mut self, set: HashSet<T, S>) {
+        self.inner.push(set);
+    }
+
+    /// Removes the lowest-precedence set from the chain and returns it, or
+    /// `None` if the chain is empty.
+    pub fn pop_set(&mut self) -> Option<HashSet<T, S>> {
+        self.inner.pop()
+    }
+
+    /// Returns the number of sets in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<T, S> ChainSet<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if any set in the chain contains the value.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().any(|set| set.contains(value))
+    }
+
+    /// Returns the index of the highest-precedence set that contains the
+    /// value, if any.
+    pub fn source_of<Q>(&self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().position(|set| set.contains(value))
+    }
+
+    /// Adds a value to the highest-precedence set in the chain, adding an
+    /// empty set first if the chain is empty.
+    ///
+    /// Returns `true` if the highest-precedence set did not already contain
+    /// the value.
+    pub fn insert(&mut self, value: T) -> bool
+    where
+        S: Default,
+    {
+        if self.inner.is_empty() {
+            self.inner.push(HashSet::default());
+        }
+        self.inner[0].insert(value)
+    }
+
+    /// Removes a value from every set in the chain, returning `true` if any
+    /// set contained it.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut removed = false;
+        for set in &mut self.inner {
+            removed |= set.remove(value);
+        }
+        removed
+    }
+
+    /// Returns the number of distinct values in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the chain contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.inner.iter().all(HashSet::is_empty)
+    }
+
+    /// An iterator visiting each distinct value in the chain once, in
+    /// arbitrary order.
+    pub fn iter(&self) -> ChainSetIter<'_, T, S> {
+        ChainSetIter {
+            sets: &self.inner,
+            index: 0,
+            current: None,
+        }
+    }
+}
+
+impl<T, S> Default for ChainSet<T, S> {
+    fn default() -> Self {
+        ChainSet::new()
+    }
+}
+
+impl<T, S> FromIterator<HashSet<T, S>> for ChainSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = HashSet<T, S>>>(iter: I) -> Self {
+        ChainSet {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T, S> Debug for ChainSet<T, S>
+where
+    T: Hash + Eq + Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the distinct values of a `ChainSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`ChainSet`]. See its
+/// documentation for more.
+///
+/// [`iter`]: struct.ChainSet.html#method.iter
+/// [`ChainSet`]: struct.ChainSet.html
+pub struct ChainSetIter<'a, T, S> {
+    sets: &'a [HashSet<T, S>],
+    index: usize,
+    current: Option<hash_set::Iter<'a, T>>,
+}
+
+impl<'a, T, S> Clone for ChainSetIter<'a, T, S> {
+    fn clone(&self) -> Self {
+        ChainSetIter {
+            sets: self.sets,
+            index: self.index,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'a, T, S> Iterator for ChainSetIter<'a, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let shadowing = &self.sets[..self.index];
+                let value = current.find(|value| !shadowing.iter().any(|set| set.contains(*value)));
+                if value.is_some() {
+                    return value;
+                }
+                self.index += 1;
+            }
+            self.current = Some(self.sets.get(self.index)?.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter_visits_each_value_once() {
+        let first: HashSet<_> = vec![1, 2].into_iter().collect();
+        let second: HashSet<_> = vec![2, 3].into_iter().collect();
+        let third: HashSet<_> = vec![1, 3, 4].into_iter().collect();
+
+        let mut chain: ChainSet<_> = vec![first, second, third].into_iter().collect();
+        let mut values: Vec<_> = chain.iter().cloned().collect();
+        values.sort();
+        assert_eq!(values, vec![1, 2, 3, 4]);
+        assert_eq!(chain.source_of(&3), Some(1));
+        assert_eq!(chain.source_of(&5), None);
+
+        assert!(chain.insert(5));
+        assert_eq!(chain.source_of(&5), Some(0));
+        assert!(chain.remove(&3));
+        assert!(!chain.contains(&3));
+        assert_eq!(chain.len(), 4);
+    }
+}
@@ -0,0 +1,476 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::ops::{Deref, Index};
+
+use crate::compat::{hash_map, Arc, HashMap, RandomState, Rc, Vec};
+
+/// A reference-counted pointer that can hold the maps of a
+/// [`SharedChainMap`].
+///
+/// This is implemented for [`Arc`] and [`Rc`].
+///
+/// [`SharedChainMap`]: struct.SharedChainMap.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`Rc`]: https://doc.rust-lang.org/std/rc/struct.Rc.html
+pub trait SharedPtr<T>: Deref<Target = T> + Clone {
+    /// Creates a new pointer holding the value.
+    fn new(value: T) -> Self;
+
+    /// Returns a mutable reference to the value, first cloning it if it is
+    /// shared with other pointers.
+    fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: Clone;
+}
+
+impl<T> SharedPtr<T> for Arc<T> {
+    fn new(value: T) -> Self {
+        Arc::new(value)
+    }
+
+    fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: Clone,
+    {
+        Arc::make_mut(this)
+    }
+}
+
+impl<T> SharedPtr<T> for Rc<T> {
+    fn new(value: T) -> Self {
+        Rc::new(value)
+    }
+
+    fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: Clone,
+    {
+        Rc::make_mut(this)
+    }
+}
+
+/// A [`SharedChainMap`] that holds its maps in an [`Rc`] rather than an
+/// [`Arc`], for chains that are only used from a single thread.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::RcChainMap;
+///
+/// let mut globals = HashMap::new();
+/// globals.insert("x", 1);
+///
+/// let mut frame = RcChainMap::default();
+/// frame.push_map(globals);
+///
+/// let mut child = frame.new_child();
+/// child.insert("x", 2);
+///
+/// assert_eq!(child["x"], 2);
+/// assert_eq!(frame["x"], 1);
+/// ```
+///
+/// [`SharedChainMap`]: struct.SharedChainMap.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`Rc`]: https://doc.rust-lang.org/std/rc/struct.Rc.html
+pub type RcChainMap<K, V, S = RandomState> = SharedChainMap<K, V, S, Rc<HashMap<K, V, S>>>;
+
+/// A chain of reference-counted [`HashMap`]s with a single view into the
+/// values.
+///
+/// Each map is held in an [`Arc`], so cloning a `SharedChainMap` only copies
+/// one pointer per map, and clones share their maps until one of them is
+/// modified. Writes use [`Arc::make_mut`], so a map that is shared with
+/// another chain is cloned before it is changed, leaving the other chain's
+/// view untouched. Maps are added in precedence order, so the first map
+/// added has the highest precedence.
+///
+/// The pointer type can be changed with the `P` parameter to any type that
+/// implements [`SharedPtr`]. [`RcChainMap`] uses [`Rc`] instead, avoiding
+/// atomic reference counts for chains that are only used from one thread.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use chain_map::SharedChainMap;
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("timeout", 30);
+/// defaults.insert("retries", 3);
+///
+/// let mut base = SharedChainMap::new();
+/// base.push_map(defaults);
+///
+/// // Each handler gets its own top layer, sharing the defaults
+/// let mut handler = base.new_child();
+/// handler.insert("timeout", 5);
+///
+/// assert_eq!(handler["timeout"], 5);
+/// assert_eq!(handler["retries"], 3);
+/// assert_eq!(base["timeout"], 30);
+/// ```
+///
+/// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`Arc::make_mut`]: https://doc.rust-lang.org/std/sync/struct.Arc.html#method.make_mut
+/// [`SharedPtr`]: trait.SharedPtr.html
+/// [`RcChainMap`]: type.RcChainMap.html
+/// [`Rc`]: https://doc.rust-lang.org/std/rc/struct.Rc.html
+pub struct SharedChainMap<K, V, S = RandomState, P = Arc<HashMap<K, V, S>>> {
+    inner: Vec<P>,
+    marker: PhantomData<HashMap<K, V, S>>,
+}
+
+impl<K, V, S> SharedChainMap<K, V, S> {
+    /// Creates an empty `SharedChainMap`.
+    ///
+    /// To create a chain with another pointer type, such as an
+    /// [`RcChainMap`], use [`Default::default`].
+    ///
+    /// [`RcChainMap`]: type.RcChainMap.html
+    /// [`Default::default`]: https://doc.rust-lang.org/std/default/trait.Default.html#tymethod.default
+    pub fn new() -> Self {
+        SharedChainMap::default()
+    }
+}
+
+impl<K, V, S, P> SharedChainMap<K, V, S, P>
+where
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    /// Adds a map to the lowest-precedence end of the chain.
+    pub fn push_map(&mut self, map: HashMap<K, V, S>) {
+        self.inner.push(P::new(map));
+    }
+
+    /// Adds a map that may be shared with other chains to the
+    /// lowest-precedence end of the chain.
+    pub fn push_shared_map(&mut self, map: P) {
+        self.inner.push(map);
+    }
+
+    /// Adds a map to the highest-precedence end of the chain.
+    pub fn push_front_map(&mut self, map: HashMap<K, V, S>) {
+        self.inner.insert(0, P::new(map));
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    pub fn pop_map(&mut self) -> Option<P> {
+        self.inner.pop()
+    }
+
+    /// Returns the shared map at the given index in the chain, or `None` if
+    /// the index is out of bounds.
+    pub fn get_map(&self, index: usize) -> Option<&P> {
+        self.inner.get(index)
+    }
+
+    /// Returns the number of maps in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K, V, S, P> SharedChainMap<K, V, S, P>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    /// Creates a new `SharedChainMap` with a new empty map at the
+    /// highest-precedence end, followed by this chain's maps.
+    ///
+    /// The maps are shared with this chain rather than copied, so this takes
+    /// O(N) time in the number of maps, regardless of their size.
+    pub fn new_child(&self) -> Self
+    where
+        S: Default,
+    {
+        let mut inner = Vec::with_capacity(self.inner.len() + 1);
+        inner.push(P::new(HashMap::default()));
+        inner.extend(self.inner.iter().cloned());
+        SharedChainMap {
+            inner,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().any(|map| map.contains_key(k))
+    }
+
+    /// Returns a reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().filter_map(|map| map.get(k)).next()
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.iter().position(|map| map.contains_key(k))
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.iter().all(|map| map.is_empty())
+    }
+
+    /// An iterator visiting all key-value pairs in the unified view, in
+    /// arbitrary order.
+    ///
+    /// Each key appears once, with its highest-precedence value.
+    pub fn iter(&self) -> SharedIter<'_, K, V, S, P> {
+        SharedIter {
+            maps: &self.inner,
+            index: 0,
+            current: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V, S, P> SharedChainMap<K, V, S, P>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    /// Returns a mutable reference to the value of the given key in the
+    /// highest-precedence map that contains it.
+    ///
+    /// If that map is shared with another chain, it is cloned first.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        P::make_mut(&mut self.inner[index]).get_mut(k)
+    }
+
+    /// Inserts a key-value pair into the highest-precedence map in the chain,
+    /// adding an empty map first if the chain is empty.
+    ///
+    /// If that map is shared with another chain, it is cloned first. Returns
+    /// the value previously associated with the key in that map, if any.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V>
+    where
+        S: Default,
+    {
+        if self.inner.is_empty() {
+            self.inner.push(P::new(HashMap::default()));
+        }
+        P::make_mut(&mut self.inner[0]).insert(k, v)
+    }
+
+    /// Removes a key from the highest-precedence map that contains it,
+    /// returning the value at the key if the key was present in the chain.
+    ///
+    /// If that map is shared with another chain, it is cloned first. A value
+    /// for the same key in a lower-precedence map will become visible.
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.source_of(k)?;
+        P::make_mut(&mut self.inner[index]).remove(k)
+    }
+}
+
+impl<K, V, S, P: Clone> Clone for SharedChainMap<K, V, S, P> {
+    fn clone(&self) -> Self {
+        SharedChainMap {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V, S, P> Default for SharedChainMap<K, V, S, P> {
+    fn default() -> Self {
+        SharedChainMap {
+            inner: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, Q, V, S, P> Index<&Q> for SharedChainMap<K, V, S, P>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<K, V, S, P> FromIterator<HashMap<K, V, S>> for SharedChainMap<K, V, S, P>
+where
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    fn from_iter<I: IntoIterator<Item = HashMap<K, V, S>>>(iter: I) -> Self {
+        SharedChainMap {
+            inner: iter.into_iter().map(P::new).collect(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V, S, P> Debug for SharedChainMap<K, V, S, P>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `SharedChainMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`SharedChainMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: struct.SharedChainMap.html#method.iter
+/// [`SharedChainMap`]: struct.SharedChainMap.html
+pub struct SharedIter<'a, K, V, S, P = Arc<HashMap<K, V, S>>> {
+    maps: &'a [P],
+    index: usize,
+    current: Option<hash_map::Iter<'a, K, V>>,
+    marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, K, V, S, P> Clone for SharedIter<'a, K, V, S, P> {
+    fn clone(&self) -> Self {
+        SharedIter {
+            maps: self.maps,
+            index: self.index,
+            current: self.current.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V, S, P> Iterator for SharedIter<'a, K, V, S, P>
+where
+    K: Hash + Eq,
+    S: BuildHasher + 'a,
+    P: SharedPtr<HashMap<K, V, S>>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let shadowing = &self.maps[..self.index];
+                let entry =
+                    current.find(|&(key, _)| !shadowing.iter().any(|map| map.contains_key(key)));
+                if entry.is_some() {
+                    return entry;
+                }
+                self.index += 1;
+            }
+            self.current = Some(self.maps.get(self.index)?.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_maps_until_written() {
+        let mut first_map = HashMap::new();
+        first_map.insert("first", 1);
+
+        let mut second_map = HashMap::new();
+        second_map.insert("first", 2);
+        second_map.insert("second", 2);
+
+        let parent: SharedChainMap<_, _> = vec![first_map, second_map].into_iter().collect();
+        let mut child = parent.clone();
+        assert!(Arc::ptr_eq(
+            parent.get_map(1).unwrap(),
+            child.get_map(1).unwrap()
+        ));
+
+        *child.get_mut("second").unwrap() += 10;
+        assert_eq!(child["second"], 12);
+        assert_eq!(parent["second"], 2);
+        assert!(!Arc::ptr_eq(
+            parent.get_map(1).unwrap(),
+            child.get_map(1).unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            parent.get_map(0).unwrap(),
+            child.get_map(0).unwrap()
+        ));
+
+        assert_eq!(child.remove("first"), Some(1));
+        assert_eq!(child["first"], 2);
+        assert_eq!(parent["first"], 1);
+
+        let mut entries: Vec<_> = child.iter().collect();
+        entries.sort();
+        assert_eq!(entries, vec![(&"first", &2), (&"second", &12)]);
+    }
+
+    #[test]
+    fn rc_chains_share_maps_between_scopes() {
+        let mut globals = HashMap::new();
+        globals.insert("x", 1);
+        globals.insert("y", 1);
+
+        let mut frame: RcChainMap<_, _> = RcChainMap::default();
+        frame.push_map(globals);
+
+        let mut child = frame.new_child();
+        child.insert("x", 2);
+        assert!(Rc::ptr_eq(
+            frame.get_map(0).unwrap(),
+            child.get_map(1).unwrap()
+        ));
+        assert_eq!(child["x"], 2);
+        assert_eq!(child["y"], 1);
+        assert_eq!(frame["x"], 1);
+
+        *child.get_mut("y").unwrap() += 10;
+        assert_eq!(frame["y"], 1);
+        assert_eq!(Rc::strong_count(frame.get_map(0).unwrap()), 1);
+    }
+}
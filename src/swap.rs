@@ -0,0 +1,317 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use arc_swap::ArcSwap;
+
+use crate::compat::{HashMap, RandomState};
+
+use super::SharedChainMap;
+
+/// A `SharedChainMap` that can be read from many threads without ever
+/// blocking.
+///
+/// The chain is held in an [`ArcSwap`]. Each lookup reads whichever version
+/// of the chain is current, without taking a lock, so readers never wait for
+/// each other or for writers. Each mutation copies the current chain, applies
+/// the change to the copy, and publishes the copy atomically, so a lookup
+/// always sees a consistent set of maps. Writers are serialized with a lock
+/// among themselves, so no update is lost.
+///
+/// Since the chain is a [`SharedChainMap`], copying it only copies one
+/// pointer per map. A map is only cloned when a mutation changes it while
+/// an earlier version still holds it, so a write costs O(N) in the number
+/// of maps plus a copy of the one map it changes. This suits read-mostly
+/// workloads, such as configuration that is resolved constantly but changes
+/// rarely. Use [`update`] to apply several changes with a single copy. This
+/// `struct` is only available with the `arc-swap` feature.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "arc-swap")]
+/// # {
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// use std::thread;
+/// use chain_map::{SharedChainMap, SwapChainMap};
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("timeout_ms", 500);
+///
+/// let chain: SharedChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+/// let shared = Arc::new(SwapChainMap::new(chain));
+///
+/// let readers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let shared = Arc::clone(&shared);
+///         thread::spawn(move || shared.get_cloned("timeout_ms").unwrap())
+///     })
+///     .collect();
+///
+/// shared.insert("timeout_ms", 250);
+///
+/// for reader in readers {
+///     let timeout = reader.join().unwrap();
+///     assert!(timeout == 500 || timeout == 250);
+/// }
+/// assert_eq!(shared.get_cloned("timeout_ms"), Some(250));
+/// # }
+/// ```
+///
+/// [`ArcSwap`]: https://docs.rs/arc-swap/1/arc_swap/type.ArcSwap.html
+/// [`SharedChainMap`]: struct.SharedChainMap.html
+/// [`update`]: #method.update
+pub struct SwapChainMap<K, V, S = RandomState> {
+    current: ArcSwap<SharedChainMap<K, V, S>>,
+    writer: Mutex<()>,
+}
+
+impl<K, V, S> SwapChainMap<K, V, S> {
+    /// Publishes the chain as the first version.
+    pub fn new(chain: SharedChainMap<K, V, S>) -> Self {
+        SwapChainMap {
+            current: ArcSwap::from_pointee(chain),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current version of the chain.
+    ///
+    /// The snapshot is not affected by later mutations, so every lookup made
+    /// on it sees the same maps.
+    pub fn snapshot(&self) -> Arc<SharedChainMap<K, V, S>> {
+        self.current.load_full()
+    }
+
+    /// Calls `f` with the current version of the chain, returning its result.
+    ///
+    /// This doesn't block writers, which publish new versions of the chain
+    /// without affecting the one `f` is reading.
+    pub fn read<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&SharedChainMap<K, V, S>) -> R,
+    {
+        f(&self.current.load())
+    }
+
+    /// Replaces the chain with a new one, returning the previous version.
+    pub fn store(&self, chain: SharedChainMap<K, V, S>) -> Arc<SharedChainMap<K, V, S>> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.current.swap(Arc::new(chain))
+    }
+
+    /// Returns the number of maps in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.read(SharedChainMap::layer_count)
+    }
+
+    /// Calls `f` with a copy of the current chain, then publishes the
+    /// modified copy as the new version, returning the result of `f`.
+    ///
+    /// The copy shares its maps with the current version, and a map is only
+    /// cloned when `f` modifies it. Lookups that started before the new
+    /// version is published still see the previous one, and no lookup sees
+    /// the changes made by `f` until it returns.
+    pub fn update<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut SharedChainMap<K, V, S>) -> R,
+    {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut chain = SharedChainMap::clone(&self.current.load());
+        let result = f(&mut chain);
+        self.current.store(Arc::new(chain));
+        result
+    }
+
+    /// Adds a map to the chain, as with [`SharedChainMap::push_map`].
+    ///
+    /// [`SharedChainMap::push_map`]: struct.SharedChainMap.html#method.push_map
+    pub fn push_map(&self, map: HashMap<K, V, S>) {
+        self.update(|chain| chain.push_map(map))
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    ///
+    /// The map may still be held by earlier versions of the chain.
+    pub fn pop_map(&self) -> Option<Arc<HashMap<K, V, S>>> {
+        self.update(SharedChainMap::pop_map)
+    }
+}
+
+impl<K, V, S> SwapChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(|chain| chain.contains_key(k))
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(|chain| chain.source_of(k))
+    }
+
+    /// Calls `f` with the highest-precedence value associated with the given
+    /// key, returning its result, or `None` if the key isn't in the chain.
+    pub fn with_value<Q, F, R>(&self, k: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&V) -> R,
+    {
+        self.read(|chain| chain.get(k).map(f))
+    }
+
+    /// Returns a clone of the highest-precedence value associated with the
+    /// given key.
+    pub fn get_cloned<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.with_value(k, V::clone)
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.read(SharedChainMap::len)
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.read(SharedChainMap::is_empty)
+    }
+}
+
+impl<K, V, S> SwapChainMap<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    /// Inserts a key-value pair into the highest-precedence map in the
+    /// chain, as with [`SharedChainMap::insert`], and publishes the result.
+    ///
+    /// [`SharedChainMap::insert`]: struct.SharedChainMap.html#method.insert
+    pub fn insert(&self, k: K, v: V) -> Option<V>
+    where
+        S: Default,
+    {
+        self.update(|chain| chain.insert(k, v))
+    }
+
+    /// Removes a key from the highest-precedence map that contains it, as
+    /// with [`SharedChainMap::remove`], and publishes the result.
+    ///
+    /// [`SharedChainMap::remove`]: struct.SharedChainMap.html#method.remove
+    pub fn remove<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.update(|chain| chain.remove(k))
+    }
+}
+
+impl<K, V, S> Default for SwapChainMap<K, V, S> {
+    fn default() -> Self {
+        SwapChainMap::new(SharedChainMap::default())
+    }
+}
+
+impl<K, V, S> From<SharedChainMap<K, V, S>> for SwapChainMap<K, V, S> {
+    fn from(chain: SharedChainMap<K, V, S>) -> Self {
+        SwapChainMap::new(chain)
+    }
+}
+
+impl<K, V, S> Debug for SwapChainMap<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.read(|chain| chain.fmt(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn snapshots_are_unaffected_by_later_updates() {
+        let mut defaults = HashMap::new();
+        defaults.insert("level", 1);
+
+        let chain: SharedChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+        let shared = Arc::new(SwapChainMap::new(chain));
+        let before = shared.snapshot();
+
+        let writers: Vec<_> = (0..4)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    shared.update(|chain| {
+                        let count = chain.get(&"writes").cloned().unwrap_or(0);
+                        chain.insert("writes", count + 1);
+                        chain.insert("level", i + 2);
+                    })
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(shared.get_cloned(&"writes"), Some(4));
+        assert_eq!(shared.source_of(&"level"), Some(0));
+        assert_eq!(before.get(&"level"), Some(&1));
+        assert!(!before.contains_key(&"writes"));
+
+        let previous = shared.store(SharedChainMap::new());
+        assert_eq!(previous.len(), 2);
+        assert!(shared.is_empty());
+    }
+
+    #[test]
+    fn updates_only_copy_the_changed_map() {
+        let mut defaults = HashMap::new();
+        defaults.insert("level", 1);
+
+        let chain: SharedChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+        let shared = SwapChainMap::new(chain);
+        let before = shared.snapshot();
+
+        shared.insert("level", 2);
+        let after = shared.snapshot();
+
+        assert!(Arc::ptr_eq(
+            before.get_map(1).unwrap(),
+            after.get_map(1).unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            before.get_map(0).unwrap(),
+            after.get_map(0).unwrap()
+        ));
+        assert_eq!(before.get(&"level"), Some(&1));
+        assert_eq!(after.get(&"level"), Some(&2));
+    }
+}
@@ -0,0 +1,258 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::sync::{PoisonError, RwLock};
+
+use crate::compat::{HashMap, RandomState};
+
+use super::ChainMap;
+
+/// A `ChainMap` behind a lock, so it can be shared between threads.
+///
+/// Lookups take a read lock on the whole chain, so any number of threads can
+/// read at once, while mutations take a write lock, so every lookup sees a
+/// consistent set of maps. Since a value can't be borrowed past the end of a
+/// lookup, values are read through a closure with [`with_value`], or cloned
+/// out with [`get_cloned`]. Use [`read`] and [`write`] to run several
+/// operations under a single lock.
+///
+/// If a thread panics while holding the lock, the chain is still available
+/// to other threads, in whatever state the panicking thread left it in.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// use std::thread;
+/// use chain_map::{ChainMap, SyncChainMap};
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("region", "us-east".to_string());
+///
+/// let chain: ChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+/// let shared = Arc::new(SyncChainMap::new(chain));
+///
+/// let writer = Arc::clone(&shared);
+/// thread::spawn(move || {
+///     writer.insert("region", "eu-west".to_string());
+/// })
+/// .join()
+/// .unwrap();
+///
+/// assert_eq!(shared.get_cloned("region"), Some("eu-west".to_string()));
+/// assert_eq!(shared.with_value("region", |region| region.len()), Some(7));
+/// ```
+///
+/// [`with_value`]: #method.with_value
+/// [`get_cloned`]: #method.get_cloned
+/// [`read`]: #method.read
+/// [`write`]: #method.write
+pub struct SyncChainMap<K, V, S = RandomState> {
+    chain: RwLock<ChainMap<K, V, S>>,
+}
+
+impl<K, V, S> SyncChainMap<K, V, S> {
+    /// Wraps the chain in a lock.
+    pub fn new(chain: ChainMap<K, V, S>) -> Self {
+        SyncChainMap {
+            chain: RwLock::new(chain),
+        }
+    }
+
+    /// Consumes the `SyncChainMap`, returning the underlying chain.
+    pub fn into_inner(self) -> ChainMap<K, V, S> {
+        self.chain
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns a mutable reference to the underlying chain.
+    ///
+    /// Since this takes `self` by mutable reference, no locking is needed.
+    pub fn get_mut(&mut self) -> &mut ChainMap<K, V, S> {
+        self.chain.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Calls `f` with a read lock held on the chain, returning its result.
+    ///
+    /// Other threads can read the chain at the same time, but can't modify it
+    /// until `f` returns.
+    pub fn read<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&ChainMap<K, V, S>) -> R,
+    {
+        f(&self.chain.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Calls `f` with a write lock held on the chain, returning its result.
+    ///
+    /// No other thread can read or modify the chain until `f` returns.
+    pub fn write<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut ChainMap<K, V, S>) -> R,
+    {
+        f(&mut self.chain.write().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Adds a map to the chain, as with [`ChainMap::push_map`].
+    ///
+    /// [`ChainMap::push_map`]: struct.ChainMap.html#method.push_map
+    pub fn push_map(&self, map: HashMap<K, V, S>) {
+        self.write(|chain| chain.push_map(map))
+    }
+
+    /// Removes the lowest-precedence map from the chain and returns it, or
+    /// `None` if the chain is empty.
+    pub fn pop_map(&self) -> Option<HashMap<K, V, S>> {
+        self.write(ChainMap::pop_map)
+    }
+
+    /// Returns the number of maps in the chain.
+    pub fn layer_count(&self) -> usize {
+        self.read(|chain| chain.inner.len())
+    }
+}
+
+impl<K, V, S> SyncChainMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if the chain contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(|chain| chain.contains_key(k))
+    }
+
+    /// Returns the index of the map that provides the value for the given
+    /// key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(|chain| chain.source_of(k))
+    }
+
+    /// Calls `f` with the highest-precedence value associated with the given
+    /// key, returning its result, or `None` if the key isn't in the chain.
+    ///
+    /// The read lock is held while `f` runs.
+    pub fn with_value<Q, F, R>(&self, k: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&V) -> R,
+    {
+        self.read(|chain| chain.get(k).map(f))
+    }
+
+    /// Returns a clone of the highest-precedence value associated with the
+    /// given key.
+    pub fn get_cloned<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.with_value(k, V::clone)
+    }
+
+    /// Inserts a key-value pair into the highest-precedence map in the
+    /// chain, as with [`ChainMap::insert`].
+    ///
+    /// [`ChainMap::insert`]: struct.ChainMap.html#method.insert
+    pub fn insert(&self, k: K, v: V) -> Option<V>
+    where
+        S: Default,
+    {
+        self.write(|chain| chain.insert(k, v))
+    }
+
+    /// Removes a key from the highest-precedence map that contains it, as
+    /// with [`ChainMap::remove`].
+    ///
+    /// [`ChainMap::remove`]: struct.ChainMap.html#method.remove
+    pub fn remove<Q>(&self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.write(|chain| chain.remove(k))
+    }
+
+    /// Returns the number of distinct keys in the chain.
+    pub fn len(&self) -> usize {
+        self.read(ChainMap::len)
+    }
+
+    /// Returns `true` if the chain contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.read(ChainMap::is_empty)
+    }
+}
+
+impl<K, V, S> Default for SyncChainMap<K, V, S> {
+    fn default() -> Self {
+        SyncChainMap::new(ChainMap::default())
+    }
+}
+
+impl<K, V, S> From<ChainMap<K, V, S>> for SyncChainMap<K, V, S> {
+    fn from(chain: ChainMap<K, V, S>) -> Self {
+        SyncChainMap::new(chain)
+    }
+}
+
+impl<K, V, S> Debug for SyncChainMap<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.read(|chain| chain.fmt(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn readers_and_writers_share_the_chain() {
+        let mut defaults = HashMap::new();
+        defaults.insert(0, 0);
+
+        let chain: ChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+        let shared = Arc::new(SyncChainMap::new(chain));
+
+        let threads: Vec<_> = (1..5)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    shared.insert(i, i * 10);
+                    assert_eq!(shared.get_cloned(&0), Some(0));
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(shared.len(), 5);
+        assert_eq!(shared.with_value(&3, |value| value + 1), Some(31));
+        assert_eq!(shared.source_of(&3), Some(0));
+        assert_eq!(shared.remove(&0), Some(0));
+        assert!(!shared.contains_key(&0));
+
+        let shared = Arc::try_unwrap(shared).unwrap();
+        assert_eq!(shared.into_inner().layer_sizes(), vec![4, 0]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Loads TOML documents into maps that can be added to a `ChainMap`.
+//!
+//! Nested tables are flattened into dotted keys, so the document
+//!
+//! ```toml
+//! [server]
+//! port = 8080
+//! ```
+//!
+//! produces a map with the single key `server.port`. This means a value in
+//! one layer only shadows the same value in lower-precedence layers, rather
+//! than the whole table that contains it. Any other values, including arrays
+//! and arrays of tables, are stored as they are.
+//!
+//! This module is only available with the `toml` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use chain_map::{toml_layer, ChainMap};
+//!
+//! let user = toml_layer::from_str("[server]\nport = 9000").unwrap();
+//! let defaults = toml_layer::from_str("[server]\nport = 8080\nhost = 'localhost'").unwrap();
+//!
+//! let chain: ChainMap<_, _> = vec![user, defaults].into_iter().collect();
+//!
+//! assert_eq!(chain["server.port"].as_integer(), Some(9000));
+//! assert_eq!(chain["server.host"].as_str(), Some("localhost"));
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use toml::{Table, Value};
+
+/// Parses a TOML document into a map, flattening nested tables into dotted
+/// keys.
+///
+/// # Errors
+///
+/// Returns an error if the document is not valid TOML.
+pub fn from_str(s: &str) -> Result<HashMap<String, Value>, toml::de::Error> {
+    let table: Table = s.parse()?;
+    let mut map = HashMap::new();
+    flatten(&mut map, String::new(), table);
+    Ok(map)
+}
+
+/// Reads and parses a TOML file into a map, flattening nested tables into
+/// dotted keys.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or is not valid TOML.
+pub fn from_path<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Value>, TomlLayerError> {
+    let contents = fs::read_to_string(path).map_err(TomlLayerError::Io)?;
+    from_str(&contents).map_err(TomlLayerError::Parse)
+}
+
+fn flatten(map: &mut HashMap<String, Value>, prefix: String, table: Table) {
+    for (key, value) in table {
+        let key = prefix.clone() + &key;
+        match value {
+            Value::Table(table) => flatten(map, key + ".", table),
+            value => {
+                map.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The error returned when a TOML file can't be loaded by [`from_path`].
+///
+/// [`from_path`]: fn.from_path.html
+#[derive(Debug)]
+pub enum TomlLayerError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The file is not valid TOML.
+    Parse(toml::de::Error),
+}
+
+impl Display for TomlLayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlLayerError::Io(error) => write!(f, "failed to read TOML file: {}", error),
+            TomlLayerError::Parse(error) => write!(f, "failed to parse TOML file: {}", error),
+        }
+    }
+}
+
+impl Error for TomlLayerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TomlLayerError::Io(error) => Some(error),
+            TomlLayerError::Parse(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn nested_tables_are_flattened() {
+        let map = from_str(
+            r#"
+            name = "app"
+            ports = [80, 443]
+
+            [server]
+            host = "localhost"
+
+            [server.tls]
+            enabled = true
+
+            [[plugins]]
+            name = "fmt"
+            "#,
+        )
+        .unwrap();
+
+        let mut keys: Vec<_> = map.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "name",
+                "plugins",
+                "ports",
+                "server.host",
+                "server.tls.enabled"
+            ]
+        );
+        assert_eq!(map["server.tls.enabled"].as_bool(), Some(true));
+        assert!(map["plugins"].is_array());
+    }
+
+    #[test]
+    fn from_path_reports_errors() {
+        let dir = env::temp_dir();
+        let path = dir.join(format!("chain-map-toml-layer-{}.toml", std::process::id()));
+
+        fs::write(&path, "key = 'value'").unwrap();
+        assert_eq!(from_path(&path).unwrap()["key"].as_str(), Some("value"));
+
+        fs::write(&path, "key = ").unwrap();
+        match from_path(&path) {
+            Err(TomlLayerError::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+
+        fs::remove_file(&path).unwrap();
+        match from_path(&path) {
+            Err(TomlLayerError::Io(_)) => {}
+            other => panic!("expected an IO error, got {:?}", other),
+        }
+    }
+}
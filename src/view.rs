@@ -0,0 +1,623 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use core::iter::Chain;
+use core::ops::Index;
+use core::slice;
+
+use crate::bloom::Probe;
+
+use super::{visible_links, Iter, Link};
+
+type VisibleLinks<'a, K, V, S> =
+    Chain<slice::Iter<'a, Link<K, V, S>>, slice::Iter<'a, Link<K, V, S>>>;
+
+/// A read-only view of a contiguous range of the maps in a `ChainMap`, or of
+/// the maps of two chains one after the other.
+///
+/// This `struct` is created by the [`view`] and [`chained_with`] methods on
+/// [`ChainMap`]. Lookups through the view behave as if the chain only
+/// contained the maps in the view, so it can answer questions such as what a
+/// value would be without the defaults, without building a separate chain.
+/// Creating a view doesn't copy any maps.
+///
+/// [`view`]: struct.ChainMap.html#method.view
+/// [`chained_with`]: struct.ChainMap.html#method.chained_with
+/// [`ChainMap`]: struct.ChainMap.html
+pub struct ChainView<'a, K, V, S> {
+    links: &'a [Link<K, V, S>],
+    // The maps of a second chain, which follow `links` in precedence.
+    rest: &'a [Link<K, V, S>],
+    offset: usize,
+}
+
+impl<'a, K, V, S> ChainView<'a, K, V, S> {
+    pub(crate) fn new(links: &'a [Link<K, V, S>], offset: usize) -> Self {
+        ChainView {
+            links,
+            rest: &[],
+            offset,
+        }
+    }
+
+    pub(crate) fn chained(links: &'a [Link<K, V, S>], rest: &'a [Link<K, V, S>]) -> Self {
+        ChainView {
+            links,
+            rest,
+            offset: 0,
+        }
+    }
+
+    /// Returns the number of maps in the view.
+    pub fn layer_count(&self) -> usize {
+        self.links.len() + self.rest.len()
+    }
+
+    /// Returns a view of the entries in this view whose keys match the
+    /// predicate.
+    ///
+    /// See [`ChainMap::filter_view`] for more.
+    ///
+    /// [`ChainMap::filter_view`]: struct.ChainMap.html#method.filter_view
+    pub fn filter_view<F>(self, predicate: F) -> FilterView<'a, K, V, S, F>
+    where
+        F: Fn(&K) -> bool,
+    {
+        FilterView {
+            view: self,
+            predicate,
+        }
+    }
+
+    /// Returns a view of this view that transforms each value as it is
+    /// read.
+    ///
+    /// See [`ChainMap::map_view`] for more.
+    ///
+    /// [`ChainMap::map_view`]: struct.ChainMap.html#method.map_view
+    pub fn map_view<F, T>(self, f: F) -> MapView<'a, K, V, S, F>
+    where
+        F: Fn(&'a V) -> T,
+    {
+        MapView { view: self, f }
+    }
+}
+
+impl<'a, K, V, S> ChainView<'a, K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns the maps in the view that can provide a value for the key, in
+    /// precedence order, stopping at the first map with a tombstone for it.
+    fn visible_links<Q>(&self, k: &Q) -> VisibleLinks<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let links = visible_links(self.links, k);
+        let masked = links
+            .last()
+            .map_or(false, |link| link.enabled && link.masks(k));
+        let rest = if masked {
+            &[]
+        } else {
+            visible_links(self.rest, k)
+        };
+        links.iter().chain(rest)
+    }
+
+    /// Returns `true` if any map in the view contains a value for the given
+    /// key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .any(|link| link.may_contain(&probe) && link.provides(k))
+    }
+
+    /// Returns a reference to the value of the given key in the
+    /// highest-precedence map in the view that contains it.
+    pub fn get<Q>(&self, k: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .filter(|link| link.may_contain(&probe))
+            .filter_map(|link| link.lookup(k))
+            .next()
+    }
+
+    /// Returns the index in the chain of the map that provides the value for
+    /// the given key within the view, if any.
+    ///
+    /// The index is relative to the whole chain rather than the view, so it
+    /// can be passed to other methods of the [`ChainMap`]. For a view created
+    /// by [`chained_with`], the maps of the second chain are numbered after
+    /// the maps of the first, as they would be after [`append`].
+    ///
+    /// [`ChainMap`]: struct.ChainMap.html
+    /// [`chained_with`]: struct.ChainMap.html#method.chained_with
+    /// [`append`]: struct.ChainMap.html#method.append
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .position(|link| link.may_contain(&probe) && link.provides(k))
+            .map(|index| self.offset + index)
+    }
+
+    /// Returns the index in the chain of the map that provides the value for
+    /// the given key, along with the key as it is stored in that map and the
+    /// value.
+    fn entry<Q>(&self, k: &Q) -> Option<(usize, &'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let probe = Probe::new(k);
+        self.visible_links(k)
+            .enumerate()
+            .filter(|&(_, link)| link.enabled && link.may_contain(&probe))
+            .filter_map(|(index, link)| {
+                let (key, value) = link.key_value(k)?;
+                Some((self.offset + index, key, value))
+            })
+            .next()
+    }
+
+    /// Returns the number of distinct keys in the view.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the view contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// An iterator visiting all key-value pairs in the view, in arbitrary
+    /// order.
+    ///
+    /// Each key appears once, with its highest-precedence value within the
+    /// view.
+    pub fn iter(&self) -> Iter<'a, K, V, S> {
+        Iter {
+            maps: self.links,
+            rest: self.rest,
+            index: 0,
+            current: None,
+        }
+    }
+}
+
+impl<'a, K, V, S> Clone for ChainView<'a, K, V, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V, S> Copy for ChainView<'a, K, V, S> {}
+
+impl<'a, K, Q, V, S> Index<&Q> for ChainView<'a, K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, k: &Q) -> &V {
+        self.get(k).expect("no entry found for key")
+    }
+}
+
+impl<'a, K, V, S> Debug for ChainView<'a, K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A read-only view of the entries of a `ChainMap` whose keys match a
+/// predicate.
+///
+/// This `struct` is created by the [`filter_view`] method on [`ChainMap`]
+/// and [`ChainView`]. Keys that don't match the predicate are hidden from
+/// every method of the view, while the chain itself is left unchanged. The
+/// predicate is checked on each access, so nothing is copied.
+///
+/// [`filter_view`]: struct.ChainMap.html#method.filter_view
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`ChainView`]: struct.ChainView.html
+pub struct FilterView<'a, K, V, S, F> {
+    view: ChainView<'a, K, V, S>,
+    predicate: F,
+}
+
+impl<'a, K, V, S, F> FilterView<'a, K, V, S, F>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    F: Fn(&K) -> bool,
+{
+    /// Returns `true` if the view contains a value for the given key.
+    ///
+    /// The predicate is checked against the key as it is stored in the
+    /// chain, so the given key may be any borrowed form of it. With the
+    /// `std` feature, finding the stored key scans the map that provides
+    /// the value, since the standard library's `HashMap` can only lend it
+    /// out on newer versions of Rust.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.source_of(k).is_some()
+    }
+
+    /// Returns a reference to the highest-precedence value of the given key,
+    /// or `None` if the key doesn't match the predicate.
+    ///
+    /// See [`contains_key`] for how the predicate is checked.
+    ///
+    /// [`contains_key`]: #method.contains_key
+    pub fn get<Q>(&self, k: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.view.entry(k) {
+            Some((_, key, value)) if (self.predicate)(key) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the index in the chain of the map that provides the value for
+    /// the given key, or `None` if the key doesn't match the predicate.
+    ///
+    /// See [`contains_key`] for how the predicate is checked.
+    ///
+    /// [`contains_key`]: #method.contains_key
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.view.entry(k) {
+            Some((index, key, _)) if (self.predicate)(key) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of distinct keys in the view.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the view contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// An iterator visiting the key-value pairs in the view whose keys match
+    /// the predicate, in arbitrary order.
+    pub fn iter(&self) -> FilterIter<'a, '_, K, V, S, F> {
+        FilterIter {
+            inner: self.view.iter(),
+            predicate: &self.predicate,
+        }
+    }
+}
+
+impl<'a, K, V, S, F> Clone for FilterView<'a, K, V, S, F>
+where
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        FilterView {
+            view: self.view,
+            predicate: self.predicate.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S, F> Debug for FilterView<'a, K, V, S, F>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+    F: Fn(&K) -> bool,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `FilterView`.
+///
+/// This `struct` is created by the [`iter`] method on [`FilterView`]. See
+/// its documentation for more.
+///
+/// [`iter`]: struct.FilterView.html#method.iter
+/// [`FilterView`]: struct.FilterView.html
+pub struct FilterIter<'a, 'b, K, V, S, F> {
+    inner: Iter<'a, K, V, S>,
+    predicate: &'b F,
+}
+
+impl<'a, 'b, K, V, S, F> Clone for FilterIter<'a, 'b, K, V, S, F> {
+    fn clone(&self) -> Self {
+        FilterIter {
+            inner: self.inner.clone(),
+            predicate: self.predicate,
+        }
+    }
+}
+
+impl<'a, 'b, K, V, S, F> Iterator for FilterIter<'a, 'b, K, V, S, F>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    F: Fn(&K) -> bool,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let predicate = self.predicate;
+        self.inner.find(|&(key, _)| predicate(key))
+    }
+}
+
+/// A read-only view of a `ChainMap` that transforms each value as it is
+/// read.
+///
+/// This `struct` is created by the [`map_view`] method on [`ChainMap`] and
+/// [`ChainView`]. The function is called with the highest-precedence value
+/// each time a value is read, so a single chain of raw values can be exposed
+/// as typed values without converting the maps. Since the results aren't
+/// cached, the function should be cheap, or its results cached by the
+/// caller.
+///
+/// [`map_view`]: struct.ChainMap.html#method.map_view
+/// [`ChainMap`]: struct.ChainMap.html
+/// [`ChainView`]: struct.ChainView.html
+pub struct MapView<'a, K, V, S, F> {
+    view: ChainView<'a, K, V, S>,
+    f: F,
+}
+
+impl<'a, K, V, S, F, T> MapView<'a, K, V, S, F>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    F: Fn(&'a V) -> T,
+{
+    /// Returns `true` if the view contains a value for the given key.
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.view.contains_key(k)
+    }
+
+    /// Returns the transformed highest-precedence value of the given key.
+    pub fn get<Q>(&self, k: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.view.get(k).map(&self.f)
+    }
+
+    /// Returns the index in the chain of the map that provides the value for
+    /// the given key, if any.
+    pub fn source_of<Q>(&self, k: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.view.source_of(k)
+    }
+
+    /// Returns the number of distinct keys in the view.
+    pub fn len(&self) -> usize {
+        self.view.len()
+    }
+
+    /// Returns `true` if the view contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.view.is_empty()
+    }
+
+    /// An iterator visiting each key in the view with its transformed
+    /// highest-precedence value, in arbitrary order.
+    pub fn iter(&self) -> MapIter<'a, '_, K, V, S, F> {
+        MapIter {
+            inner: self.view.iter(),
+            f: &self.f,
+        }
+    }
+}
+
+impl<'a, K, V, S, F> Clone for MapView<'a, K, V, S, F>
+where
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        MapView {
+            view: self.view,
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, S, F, T> Debug for MapView<'a, K, V, S, F>
+where
+    K: Eq + Hash + Debug,
+    S: BuildHasher,
+    F: Fn(&'a V) -> T,
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of a `MapView`.
+///
+/// This `struct` is created by the [`iter`] method on [`MapView`]. See its
+/// documentation for more.
+///
+/// [`iter`]: struct.MapView.html#method.iter
+/// [`MapView`]: struct.MapView.html
+pub struct MapIter<'a, 'b, K, V, S, F> {
+    inner: Iter<'a, K, V, S>,
+    f: &'b F,
+}
+
+impl<'a, 'b, K, V, S, F> Clone for MapIter<'a, 'b, K, V, S, F> {
+    fn clone(&self) -> Self {
+        MapIter {
+            inner: self.inner.clone(),
+            f: self.f,
+        }
+    }
+}
+
+impl<'a, 'b, K, V, S, F, T> Iterator for MapIter<'a, 'b, K, V, S, F>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    F: Fn(&'a V) -> T,
+{
+    type Item = (&'a K, T);
+
+    fn next(&mut self) -> Option<(&'a K, T)> {
+        let f = self.f;
+        self.inner.next().map(|(key, value)| (key, f(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compat::{HashMap, String, Vec};
+    use crate::ChainMap;
+
+    #[test]
+    fn filter_view_hides_keys_outside_namespace() {
+        let mut overrides = HashMap::new();
+        overrides.insert("db.host", "db.internal");
+        overrides.insert("log.level", "debug");
+
+        let mut defaults = HashMap::new();
+        defaults.insert("db.host", "localhost");
+        defaults.insert("db.port", "5432");
+        defaults.insert("log.level", "info");
+
+        let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+        let db = chain.filter_view(|key: &&str| key.starts_with("db."));
+
+        assert_eq!(db.get(&"db.host"), Some(&"db.internal"));
+        assert_eq!(db.source_of(&"db.port"), Some(1));
+        assert_eq!(db.get(&"log.level"), None);
+        assert!(!db.contains_key(&"log.level"));
+
+        let mut keys: Vec<_> = db.iter().map(|(key, _)| *key).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["db.host", "db.port"]);
+
+        let db_defaults = chain
+            .view(1..)
+            .filter_view(|key: &&str| key.starts_with("db."));
+        assert_eq!(db_defaults.get(&"db.host"), Some(&"localhost"));
+        assert_eq!(db_defaults.len(), 2);
+    }
+
+    #[test]
+    fn filter_view_takes_borrowed_keys() {
+        let mut overrides = HashMap::new();
+        overrides.insert(String::from("db.host"), 1);
+        overrides.insert(String::from("log.level"), 1);
+
+        let mut defaults = HashMap::new();
+        defaults.insert(String::from("db.port"), 2);
+
+        let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+        let db = chain.filter_view(|key: &String| key.starts_with("db."));
+
+        assert_eq!(db.get("db.host"), Some(&1));
+        assert_eq!(db.source_of("db.port"), Some(1));
+        assert!(db.contains_key("db.port"));
+        assert_eq!(db.get("log.level"), None);
+        assert_eq!(db.source_of("log.level"), None);
+        assert!(!db.contains_key("db.missing"));
+    }
+
+    #[test]
+    fn map_view_transforms_values_on_access() {
+        let mut overrides = HashMap::new();
+        overrides.insert("retries", "5");
+
+        let mut defaults = HashMap::new();
+        defaults.insert("retries", "3");
+        defaults.insert("timeout", "30");
+        defaults.insert("name", "worker");
+
+        let chain: ChainMap<_, _> = vec![overrides, defaults].into_iter().collect();
+        let numbers = chain.map_view(|value: &&str| value.parse::<u32>().ok());
+
+        assert_eq!(numbers.get("retries"), Some(Some(5)));
+        assert_eq!(numbers.get("name"), Some(None));
+        assert_eq!(numbers.get("missing"), None);
+        assert_eq!(numbers.source_of("timeout"), Some(1));
+
+        let mut parsed: Vec<_> = numbers
+            .iter()
+            .filter_map(|(key, value)| value.map(|value| (*key, value)))
+            .collect();
+        parsed.sort();
+        assert_eq!(parsed, vec![("retries", 5), ("timeout", 30)]);
+
+        let defaults = chain.view(1..).map_view(|value: &&str| value.len());
+        assert_eq!(defaults.get("retries"), Some(1));
+    }
+
+    #[test]
+    fn view_only_reads_maps_in_range() {
+        let maps = (0..4).map(|index| {
+            let mut map = HashMap::new();
+            map.insert("shared", index);
+            map.insert(["zero", "one", "two", "three"][index], index);
+            map
+        });
+        let mut chain: ChainMap<_, _> = maps.collect();
+        chain.insert_tombstone("two");
+
+        let middle = chain.view(1..=2);
+        assert_eq!(middle.layer_count(), 2);
+        assert_eq!(middle.get("shared"), Some(&1));
+        assert_eq!(middle.source_of("two"), Some(2));
+        assert!(!middle.contains_key("zero"));
+        assert!(!middle.contains_key("three"));
+        assert_eq!(middle.len(), 3);
+
+        // The tombstone in the first map is outside of the view
+        assert!(!chain.contains_key("two"));
+        assert_eq!(chain.view(..).get("two"), None);
+        assert!(chain.view(2..2).is_empty());
+    }
+}
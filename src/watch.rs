@@ -0,0 +1,387 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::hash::{BuildHasher, Hash};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::ChainMap;
+
+/// A `ChainMap` with one map that is loaded from a file, and reloaded
+/// whenever the file changes.
+///
+/// A background thread polls the file for changes every `interval`. When
+/// the file changes, it is read and parsed with the loader function, and a
+/// new copy of the chain with the reloaded map is swapped in as a whole.
+/// Lookups are made on a [`snapshot`] of the chain, so they always see a
+/// consistent set of maps, even if the file is reloaded part way through.
+/// If the file can't be read or parsed, the previous contents are kept and
+/// the error is available from [`last_error`]. To avoid reading a file part
+/// way through being written, replace it in one step, for example by writing
+/// a temporary file and renaming it over the original.
+///
+/// The file is watched by polling rather than through the operating system's
+/// file change notifications, so each `WatchedChainMap` runs its own thread,
+/// and a change may take up to `interval` to be picked up. A change is
+/// detected by comparing the file's modification time and length with those
+/// seen on the previous check. The contents are not compared, so a rewrite
+/// that keeps the same length and lands within the resolution of the file
+/// system's timestamps can be missed until the file changes again.
+///
+/// The background thread is woken and stopped when the `WatchedChainMap` is
+/// dropped, and the drop waits for it to finish any reload in progress. This
+/// `struct` is only available with the `watch` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use std::time::Duration;
+/// use chain_map::{ChainMap, WatchedChainMap};
+///
+/// fn parse(contents: &str) -> Result<HashMap<String, String>, String> {
+///     contents
+///         .lines()
+///         .map(|line| match line.find('=') {
+///             Some(split) => Ok((line[..split].to_string(), line[split + 1..].to_string())),
+///             None => Err(format!("invalid line: {}", line)),
+///         })
+///         .collect()
+/// }
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("theme".to_string(), "light".to_string());
+///
+/// let chain: ChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+/// let watched =
+///     WatchedChainMap::new(chain, 0, "settings.conf", parse, Duration::from_secs(1)).unwrap();
+///
+/// let snapshot = watched.snapshot();
+/// println!("theme = {}", snapshot["theme"]);
+/// ```
+///
+/// [`snapshot`]: #method.snapshot
+/// [`last_error`]: #method.last_error
+pub struct WatchedChainMap<K, V, S = RandomState> {
+    shared: Arc<Shared<K, V, S>>,
+    path: PathBuf,
+    watcher: Option<JoinHandle<()>>,
+}
+
+struct Shared<K, V, S> {
+    current: RwLock<Arc<ChainMap<K, V, S>>>,
+    generation: AtomicUsize,
+    last_error: Mutex<Option<String>>,
+    stopped: AtomicBool,
+}
+
+impl<K, V, S> WatchedChainMap<K, V, S>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    /// Loads the file at `path` into the map at `index` in the chain, and
+    /// starts watching it for changes.
+    ///
+    /// The loader is called with the contents of the file each time it
+    /// changes, and should return the map to use in its place.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with the kind `InvalidInput` if `index` is out of
+    /// bounds. Otherwise, returns an error if the file can't be read the
+    /// first time, or if the loader fails to parse it. Errors from the
+    /// loader are returned with the kind `InvalidData`.
+    pub fn new<P, F, E>(
+        chain: ChainMap<K, V, S>,
+        index: usize,
+        path: P,
+        loader: F,
+        interval: Duration,
+    ) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        F: Fn(&str) -> Result<HashMap<K, V, S>, E> + Send + 'static,
+        E: Display,
+    {
+        if index >= chain.inner.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "index {} out of bounds for a chain of {} maps",
+                    index,
+                    chain.inner.len()
+                ),
+            ));
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let shared = Arc::new(Shared {
+            current: RwLock::new(Arc::new(chain)),
+            generation: AtomicUsize::new(0),
+            last_error: Mutex::new(None),
+            stopped: AtomicBool::new(false),
+        });
+
+        let mut stamp = file_stamp(&path)?;
+        shared.reload(index, &path, &loader)?;
+
+        let watcher = Arc::clone(&shared);
+        let watched_path = path.clone();
+        let handle = thread::Builder::new()
+            .name("chain-map-watch".to_string())
+            .spawn(move || {
+                while !watcher.stopped.load(Ordering::Acquire) {
+                    // Parked rather than asleep, so dropping the
+                    // `WatchedChainMap` can wake the thread to stop it
+                    let deadline = Instant::now() + interval;
+                    loop {
+                        let now = Instant::now();
+                        if watcher.stopped.load(Ordering::Acquire) || now >= deadline {
+                            break;
+                        }
+                        thread::park_timeout(deadline - now);
+                    }
+                    if watcher.stopped.load(Ordering::Acquire) {
+                        break;
+                    }
+
+                    match file_stamp(&watched_path) {
+                        Ok(latest) if latest != stamp => stamp = latest,
+                        _ => continue,
+                    }
+
+                    let result = watcher.reload(index, &watched_path, &loader);
+                    *watcher
+                        .last_error
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner) =
+                        result.err().map(|error| error.to_string());
+                }
+            })?;
+
+        Ok(WatchedChainMap {
+            shared,
+            path,
+            watcher: Some(handle),
+        })
+    }
+}
+
+impl<K, V, S> WatchedChainMap<K, V, S> {
+    /// Returns the current contents of the chain.
+    ///
+    /// The snapshot is not affected by later reloads, so every lookup made on
+    /// it sees the same maps. Call `snapshot` again to see the latest
+    /// contents of the file.
+    pub fn snapshot(&self) -> Arc<ChainMap<K, V, S>> {
+        let current = self
+            .shared
+            .current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&current)
+    }
+
+    /// Returns the number of times the file has been successfully loaded.
+    ///
+    /// The initial load in [`new`] counts as the first generation, so this
+    /// is `1` until the file is reloaded.
+    ///
+    /// [`new`]: #method.new
+    pub fn generation(&self) -> usize {
+        self.shared.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns the error from the most recent attempt to reload the file, or
+    /// `None` if it succeeded.
+    pub fn last_error(&self) -> Option<String> {
+        self.shared
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the path of the file being watched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<K, V, S> Shared<K, V, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    /// Reads the file and swaps in a copy of the chain with the new map.
+    fn reload<F, E>(&self, index: usize, path: &Path, loader: &F) -> io::Result<()>
+    where
+        F: Fn(&str) -> Result<HashMap<K, V, S>, E>,
+        E: Display,
+    {
+        let contents = fs::read_to_string(path)?;
+        let map = loader(&contents)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let mut chain = ChainMap::clone(&current);
+        {
+            let link = &mut chain.inner[index];
+            link.map = map;
+            if link.bloom.is_some() {
+                link.build_bloom();
+            }
+        }
+        *current = Arc::new(chain);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+}
+
+/// Identifies a version of a file by its modification time and length, since
+/// the modification time alone may be too coarse to notice quick changes.
+/// Two versions with the same length written within the same timestamp are
+/// not told apart.
+fn file_stamp(path: &Path) -> io::Result<(SystemTime, u64)> {
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.modified()?, metadata.len()))
+}
+
+impl<K, V, S> Drop for WatchedChainMap<K, V, S> {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        if let Some(watcher) = self.watcher.take() {
+            watcher.thread().unpark();
+            // A panic in the loader has already stopped the thread
+            let _ = watcher.join();
+        }
+    }
+}
+
+impl<K, V, S> Debug for WatchedChainMap<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchedChainMap")
+            .field("path", &self.path)
+            .field("chain", &self.snapshot())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn parse(contents: &str) -> Result<HashMap<String, i32>, String> {
+        contents
+            .lines()
+            .map(|line| {
+                let mut parts = line.splitn(2, '=');
+                let key = parts.next().unwrap_or_default().to_string();
+                let value = parts
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("invalid line: {}", line))?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    /// Replaces the file in one step, so the watcher never sees it part way
+    /// through being written.
+    fn replace(path: &Path, contents: &str) {
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, contents).unwrap();
+        fs::rename(&temp, path).unwrap();
+    }
+
+    fn wait_for<F: Fn() -> bool>(condition: F) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn reloads_when_file_changes() {
+        let path = env::temp_dir().join(format!("chain-map-watch-{}.conf", std::process::id()));
+        replace(&path, "key=1");
+
+        let mut defaults = HashMap::new();
+        defaults.insert("key".to_string(), 0);
+        defaults.insert("other".to_string(), 0);
+
+        let chain: ChainMap<_, _> = vec![HashMap::new(), defaults].into_iter().collect();
+        let watched =
+            WatchedChainMap::new(chain, 0, &path, parse, Duration::from_millis(5)).unwrap();
+
+        let before = watched.snapshot();
+        assert_eq!(before["key"], 1);
+        assert_eq!(watched.generation(), 1);
+
+        replace(&path, "key=2\nother=2");
+        wait_for(|| watched.generation() == 2);
+        assert_eq!(watched.snapshot()["other"], 2);
+        assert_eq!(before["other"], 0);
+
+        replace(&path, "not valid");
+        wait_for(|| watched.last_error().is_some());
+        assert_eq!(watched.generation(), 2);
+        assert_eq!(watched.snapshot()["key"], 2);
+
+        drop(watched);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn drop_stops_the_watcher_without_waiting_for_the_interval() {
+        let path = env::temp_dir().join(format!("chain-map-drop-{}.conf", std::process::id()));
+        replace(&path, "key=1");
+
+        let chain: ChainMap<_, _> = vec![HashMap::new()].into_iter().collect();
+        let watched =
+            WatchedChainMap::new(chain, 0, &path, parse, Duration::from_secs(3600)).unwrap();
+        let shared = Arc::downgrade(&watched.shared);
+
+        let start = Instant::now();
+        drop(watched);
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(shared.upgrade().is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn initial_load_errors_are_returned() {
+        let missing = env::temp_dir().join("chain-map-watch-missing.conf");
+        let chain: ChainMap<_, _> = vec![HashMap::new()].into_iter().collect();
+
+        let error =
+            WatchedChainMap::new(chain, 0, &missing, parse, Duration::from_secs(1)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn out_of_bounds_index_is_an_error() {
+        let missing = env::temp_dir().join("chain-map-watch-missing.conf");
+        let chain: ChainMap<_, _> = vec![HashMap::new()].into_iter().collect();
+
+        let error =
+            WatchedChainMap::new(chain, 1, &missing, parse, Duration::from_secs(1)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}